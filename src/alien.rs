@@ -5,6 +5,7 @@ use crate::assets::*;
 use crate::explosion::*;
 use crate::ggez_utility::*;
 use crate::level::*;
+use crate::problem::*;
 use crate::turret::*;
 use ggez::Context;
 
@@ -18,35 +19,101 @@ pub struct Alien {
     pub operation: Operation,
     pub speed: f32,
     pub pos: na::Point2<f32>,
-    pub text: graphics::Text,
-    pub answer: i32,
+    pub problem: Problem,
+    // everything typed while targeting this alien
+    pub attempts: Vec<String>,
+    // millis since the alien came on screen
+    pub time_ms: f32,
     pub explosion: Explosion,
     pub state: AlienState,
-    pub src_pixel_width: f32,
-    pub src_pixel_height: f32,
+    // show the answer with the problem, after too many wrong answers
+    pub revealed: bool,
+    // a boss's problems still to come after this one
+    pub chain: Vec<Problem>,
+    pub max_health: usize,
+    // millis left on the laser after a boss is hit
+    pub hit_ms: f32,
+}
+
+pub const BOSS_HIT_MS: f32 = 300.0;
+
+/// An alien's problem as drawn. The game keeps one per alien and only
+/// rebuilds it when the problem changes or its answer is revealed, since
+/// the Simulation has no ggez context to build text with.
+pub struct AlienText {
+    display_text: String,
+    revealed: bool,
+    pub text: graphics::Text,
 }
-impl Scalable for Alien {
+
+impl AlienText {
+    pub fn new(alien: &Alien, assets: &Assets) -> AlienText {
+        let shown = if alien.revealed {
+            alien.problem.revealed_text()
+        } else {
+            alien.problem.display_text.clone()
+        };
+        AlienText {
+            display_text: alien.problem.display_text.clone(),
+            revealed: alien.revealed,
+            text: graphics::Text::new((shown, assets.number_font, 24.0)),
+        }
+    }
+
+    pub fn is_for(&self, alien: &Alien) -> bool {
+        self.revealed == alien.revealed && self.display_text == alien.problem.display_text
+    }
+}
+impl Positioned for Alien {
     fn pct_pos(&self) -> na::Point2<f32> {
         self.pos
     }
     fn pct_dimensions(&self) -> (f32, f32) {
-        (0.045, 0.07)
-    }
-    fn src_pixel_dimensions(&self) -> (f32, f32) {
-        (self.src_pixel_width, self.src_pixel_height)
+        if self.is_boss() {
+            (0.09, 0.14)
+        } else {
+            (0.045, 0.07)
+        }
     }
 }
 impl Alien {
-    pub fn update(&mut self, turret: &mut Turret, ctx: &mut Context, dt: std::time::Duration) {
+    pub fn is_boss(&self) -> bool {
+        self.max_health > 1
+    }
+
+    // right answers still needed to destroy it
+    pub fn health(&self) -> usize {
+        self.chain.len() + 1
+    }
+
+    /// Moves a boss on to its next problem after a right answer
+    pub fn next_problem(&mut self) {
+        self.problem = self.chain.remove(0);
+        self.attempts.clear();
+        self.time_ms = 0.0;
+        self.revealed = false;
+        self.hit_ms = BOSS_HIT_MS;
+    }
+
+    pub fn update(&mut self, turret: &mut Turret, dt: std::time::Duration) {
         if self.state != AlienState::Dead {
             let sec = dt.as_millis() as f32 / 100000.0;
+            if self.state == AlienState::Alive && self.pos[1] >= 0.0 {
+                self.time_ms += dt.as_millis() as f32;
+            }
             if self.pos[1] < 0.07 {
                 self.pos = self.pos + na::Vector2::new(0.0, self.speed * 3. * sec);
             } else {
                 self.pos = self.pos + na::Vector2::new(0.0, self.speed * sec);
             }
             if self.state == AlienState::Exploding {
-                self.explosion.update(ctx, dt);
+                self.explosion.update(dt);
+            }
+            if self.hit_ms > 0.0 {
+                self.hit_ms -= dt.as_millis() as f32;
+                if self.hit_ms <= 0.0 {
+                    turret.state = TurretState::Resting;
+                }
             }
             if self.explosion.elapsed > self.explosion.duration {
                 self.state = AlienState::Dead;
@@ -55,23 +122,35 @@ impl Alien {
         }
     }
 
-    pub fn draw(&mut self, ctx: &mut Context, assets: &mut Assets) {
+    pub fn draw(&mut self, text: &graphics::Text, ctx: &mut Context, assets: &mut Assets) {
         if self.state != AlienState::Dead {
             if self.explosion.elapsed < self.explosion.duration / 2.0 {
-                let params = DrawParam::new()
-                    .color(Color::from((255, 255, 255, 255)))
-                    .dest(self.pixel_pos(graphics::size(ctx)))
-                    .scale(self.scale(graphics::size(ctx)))
-                    .offset(na::Point2::new(0.5, 0.5));
                 let img = match self.operation {
+                    _ if self.is_boss() => &assets.boss_ship,
                     Operation::Add => &assets.add_ship,
                     Operation::Subtract => &assets.sub_ship,
                     Operation::Multiply => &assets.mul_ship,
                     Operation::Divide => &assets.div_ship,
+                    Operation::Expression => &assets.expression_ship,
+                    Operation::Fraction => &assets.fraction_ship,
+                    Operation::Decimal => &assets.decimal_ship,
+                    Operation::Compare
+                    | Operation::Round
+                    | Operation::Prime
+                    | Operation::PlaceValue => &assets.number_sense_ship,
+                    Operation::Power => &assets.power_ship,
+                    Operation::SquareRoot => &assets.square_root_ship,
+                    Operation::Modulo => &assets.modulo_ship,
+                    Operation::DivideWithRemainder => &assets.remainder_ship,
                 };
+                let params = DrawParam::new()
+                    .color(Color::from((255, 255, 255, 255)))
+                    .dest(self.pixel_pos(graphics::size(ctx)))
+                    .scale(self.scale_from(image_dimensions(img), graphics::size(ctx)))
+                    .offset(na::Point2::new(0.5, 0.5));
                 let _ = graphics::draw(ctx, img, params);
 
-                let tw = self.text.width(ctx) as f32;
+                let tw = text.width(ctx) as f32;
                 let (sw, sh) = self.dest_pixel_dimensions(graphics::size(ctx));
                 let offsetx = -sw / 2.0 + (sw - tw) / 2.0;
                 let offsety = -sh / 1.2;
@@ -81,7 +160,11 @@ impl Alien {
                 let text_param = DrawParam::new()
                     .color(Color::from((255, 255, 255, 255)))
                     .dest(self.pixel_pos(graphics::size(ctx)) + offset);
-                let _ = graphics::draw(ctx, &self.text, text_param);
+                let _ = graphics::draw(ctx, text, text_param);
+
+                if self.is_boss() {
+                    self.draw_health_bar(ctx);
+                }
             }
         }
 
@@ -90,4 +173,21 @@ impl Alien {
             self.explosion.draw(ctx, assets);
         }
     }
+
+    // under the ship, red for the health already lost
+    fn draw_health_bar(&self, ctx: &mut Context) {
+        let (sw, sh) = self.dest_pixel_dimensions(graphics::size(ctx));
+        let pos = self.pixel_pos(graphics::size(ctx));
+        let left = pos[0] - sw / 2.0;
+        let top = pos[1] + sh / 2.0 + 4.0;
+        let pct = self.health() as f32 / self.max_health as f32;
+        let lost = graphics::Rect::new(left, top, sw, 6.0);
+        let left_over = graphics::Rect::new(left, top, sw * pct, 6.0);
+        for (rect, color) in &[(lost, RED), (left_over, Color::from((0, 255, 0, 255)))] {
+            let bar = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), *rect, *color);
+            if let Ok(bar) = bar {
+                let _ = graphics::draw(ctx, &bar, DrawParam::default());
+            }
+        }
+    }
 }
@@ -0,0 +1,241 @@
+use crate::answer::*;
+use crate::level::*;
+use crate::rational::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+pub const SESSION_LOG_FILE: &str = "sessions.jsonl";
+
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq)]
+pub enum Outcome {
+    Correct,
+    ReachedBottom,
+}
+
+/// How one alien was resolved, appended to the profile's session log
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct AnswerRecord {
+    // when the game was started, groups records into sessions
+    pub session: u64,
+    // what the problems were generated from, replay with MATH_DEFENSE_SEED
+    #[serde(default)]
+    pub seed: u64,
+    pub timestamp: u64,
+    pub level_id: String,
+    pub wave: usize,
+    // the tier's name, or Normal for practice and mad minutes
+    pub difficulty: String,
+    pub operation: Operation,
+    pub operands: Vec<Rational>,
+    pub problem: String,
+    pub answer: Answer,
+    // everything typed at this alien in order, wrong answers included
+    pub attempts: Vec<String>,
+    // from when the alien came on screen until it was resolved
+    pub time_ms: u32,
+    pub outcome: Outcome,
+}
+
+impl AnswerRecord {
+    pub fn wrong_attempts(&self) -> usize {
+        match self.outcome {
+            Outcome::Correct => self.attempts.len().saturating_sub(1),
+            Outcome::ReachedBottom => self.attempts.len(),
+        }
+    }
+}
+
+/// Per problem totals across a log, to spot the facts a child struggles with
+#[derive(Serialize, Debug, Default)]
+pub struct FactSummary {
+    pub problem: String,
+    pub seen: usize,
+    pub correct: usize,
+    pub reached_bottom: usize,
+    pub wrong_attempts: usize,
+    pub average_time_ms: u32,
+}
+
+pub fn append_record(profile_dir: &Path, record: &AnswerRecord) {
+    fn append_helper(profile_dir: &Path, record: &AnswerRecord) -> Result<(), String> {
+        fs::create_dir_all(profile_dir)
+            .map_err(|e| format!("couldn't create {}\n{}", profile_dir.display(), e))?;
+        let serialized = serde_json::to_string(record).map_err(|_| "couldn't serialize answer")?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(profile_dir.join(SESSION_LOG_FILE))
+            .map_err(|_| "couldn't open session log")?;
+        writeln!(file, "{}", serialized).map_err(|_| "couldn't write to session log")?;
+        Ok(())
+    }
+    if let Err(msg) = append_helper(profile_dir, record) {
+        println!("{}", msg);
+    }
+}
+
+/// Reads a profile's whole log, skipping any lines that don't parse
+pub fn load_records(profile_dir: &Path) -> Vec<AnswerRecord> {
+    match File::open(profile_dir.join(SESSION_LOG_FILE)) {
+        Ok(file) => BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn summarize(records: &[AnswerRecord]) -> Vec<FactSummary> {
+    let mut facts: BTreeMap<String, (FactSummary, u64)> = BTreeMap::new();
+    for record in records {
+        let (fact, total_time) = facts
+            .entry(record.problem.clone())
+            .or_insert_with(|| (FactSummary::default(), 0));
+        fact.problem = record.problem.clone();
+        fact.seen += 1;
+        fact.wrong_attempts += record.wrong_attempts();
+        match record.outcome {
+            Outcome::Correct => fact.correct += 1,
+            Outcome::ReachedBottom => fact.reached_bottom += 1,
+        }
+        *total_time += record.time_ms as u64;
+    }
+    let mut summaries: Vec<FactSummary> = facts
+        .into_iter()
+        .map(|(_, (mut fact, total_time))| {
+            fact.average_time_ms = (total_time / fact.seen as u64) as u32;
+            fact
+        })
+        .collect();
+    // hardest first
+    summaries.sort_by_key(|fact| {
+        std::cmp::Reverse((
+            fact.reached_bottom + fact.wrong_attempts,
+            fact.average_time_ms,
+        ))
+    });
+    summaries
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn export_csv(records: &[AnswerRecord], path: &Path) -> Result<(), String> {
+    let mut file =
+        File::create(path).map_err(|e| format!("couldn't create {}\n{}", path.display(), e))?;
+    let write_error = |e: std::io::Error| format!("couldn't write to {}\n{}", path.display(), e);
+    writeln!(
+        file,
+        "session,seed,timestamp,level_id,wave,difficulty,operation,problem,answer,attempts,wrong_attempts,time_ms,outcome"
+    )
+    .map_err(write_error)?;
+    for record in records {
+        let fields = [
+            record.session.to_string(),
+            record.seed.to_string(),
+            record.timestamp.to_string(),
+            record.level_id.clone(),
+            (record.wave + 1).to_string(),
+            record.difficulty.clone(),
+            format!("{:?}", record.operation),
+            record.problem.clone(),
+            record.answer.to_string(),
+            record.attempts.join(" "),
+            record.wrong_attempts().to_string(),
+            record.time_ms.to_string(),
+            format!("{:?}", record.outcome),
+        ];
+        let line: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        writeln!(file, "{}", line.join(",")).map_err(write_error)?;
+    }
+    Ok(())
+}
+
+pub fn export_json(records: &[AnswerRecord], path: &Path) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct Export<'a> {
+        facts: Vec<FactSummary>,
+        records: &'a [AnswerRecord],
+    }
+    let export = Export {
+        facts: summarize(records),
+        records,
+    };
+    let serialized =
+        serde_json::to_string_pretty(&export).map_err(|_| "couldn't serialize answers")?;
+    let mut file =
+        File::create(path).map_err(|e| format!("couldn't create {}\n{}", path.display(), e))?;
+    file.write_all(serialized.as_bytes())
+        .map_err(|e| format!("couldn't write to {}\n{}", path.display(), e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn record(problem: &str, attempts: &[&str], time_ms: u32, outcome: Outcome) -> AnswerRecord {
+        AnswerRecord {
+            session: 1,
+            seed: 2,
+            timestamp: 3,
+            level_id: "addition-attack".to_string(),
+            wave: 0,
+            difficulty: "Rookie".to_string(),
+            operation: Operation::Add,
+            operands: vec![Rational::from(3), Rational::from(4)],
+            problem: problem.to_string(),
+            answer: Answer::Number(Rational::from(7)),
+            attempts: attempts.iter().map(|attempt| attempt.to_string()).collect(),
+            time_ms,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn csv_fields_are_escaped() {
+        let path = env::temp_dir().join(format!("mathdefense-export-{}.csv", process::id()));
+        let records = [record("say \"3+4\", now", &["1,5", "7"], 2500, Outcome::Correct)];
+        export_csv(&records, &path).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[1],
+            "1,2,3,addition-attack,1,Rookie,Add,\"say \"\"3+4\"\", now\",7,\"1,5 7\",1,2500,Correct"
+        );
+    }
+
+    #[test]
+    fn summaries_total_each_problem_hardest_first() {
+        let records = [
+            record("2+2", &["4"], 1000, Outcome::Correct),
+            record("3+4", &["8", "7"], 2000, Outcome::Correct),
+            record("3+4", &["6"], 4000, Outcome::ReachedBottom),
+        ];
+        let summaries = summarize(&records);
+        assert_eq!(summaries.len(), 2);
+        let hardest = &summaries[0];
+        assert_eq!(hardest.problem, "3+4");
+        assert_eq!(hardest.seen, 2);
+        assert_eq!(hardest.correct, 1);
+        assert_eq!(hardest.reached_bottom, 1);
+        assert_eq!(hardest.wrong_attempts, 2);
+        assert_eq!(hardest.average_time_ms, 3000);
+        assert_eq!(summaries[1].problem, "2+2");
+        assert_eq!(summaries[1].wrong_attempts, 0);
+    }
+}
@@ -0,0 +1,101 @@
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::rational::*;
+
+/// What a problem wants typed: a number, which can be a fraction or
+/// decimal, y/n for yes or no questions, or a quotient and remainder
+/// like 7r2. 6/8 and 0.75 both equal Number(3/4).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Answer {
+    Number(Rational),
+    YesNo(bool),
+    Remainder { quotient: i32, remainder: i32 },
+}
+
+impl Answer {
+    pub fn number(&self) -> Option<Rational> {
+        match self {
+            Answer::Number(n) => Some(*n),
+            Answer::YesNo(_) | Answer::Remainder { .. } => None,
+        }
+    }
+}
+
+fn parse_yes_no(s: &str) -> Option<bool> {
+    match s.trim().to_lowercase().as_str() {
+        "y" | "yes" => Some(true),
+        "n" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+impl From<Rational> for Answer {
+    fn from(n: Rational) -> Answer {
+        Answer::Number(n)
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::YesNo(true) => write!(f, "y"),
+            Answer::YesNo(false) => write!(f, "n"),
+            Answer::Remainder {
+                quotient,
+                remainder,
+            } => write!(f, "{}r{}", quotient, remainder),
+        }
+    }
+}
+
+fn parse_remainder(s: &str) -> Option<Answer> {
+    let r = s.find('r')?;
+    Some(Answer::Remainder {
+        quotient: s[..r].trim().parse().ok()?,
+        remainder: s[r + 1..].trim().parse().ok()?,
+    })
+}
+
+/// Reads what the player typed, a number like 3/4, y/n or 7r2
+impl FromStr for Answer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Answer, String> {
+        if let Some(yes) = parse_yes_no(s) {
+            return Ok(Answer::YesNo(yes));
+        }
+        if s.contains('r') {
+            return parse_remainder(s).ok_or_else(|| format!("not a remainder {}", s));
+        }
+        s.parse::<Rational>().map(Answer::Number)
+    }
+}
+
+// saved like a Rational, with y and n for yes or no and 7r2 for remainders
+impl Serialize for Answer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Answer::Number(n) => n.serialize(serializer),
+            _ => serializer.serialize_str(&self.to_string()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Answer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Answer, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Saved {
+            Number(i32),
+            Text(String),
+        }
+        match Saved::deserialize(deserializer)? {
+            Saved::Number(n) => Ok(Answer::Number(Rational::from(n))),
+            Saved::Text(text) => text.parse::<Answer>().map_err(de::Error::custom),
+        }
+    }
+}
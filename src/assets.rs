@@ -7,6 +7,15 @@ pub struct Assets {
     pub sub_ship: graphics::Image,
     pub mul_ship: graphics::Image,
     pub div_ship: graphics::Image,
+    pub boss_ship: graphics::Image,
+    pub expression_ship: graphics::Image,
+    pub fraction_ship: graphics::Image,
+    pub decimal_ship: graphics::Image,
+    pub number_sense_ship: graphics::Image,
+    pub power_ship: graphics::Image,
+    pub square_root_ship: graphics::Image,
+    pub modulo_ship: graphics::Image,
+    pub remainder_ship: graphics::Image,
     pub crosshair: graphics::Image,
     pub title_font: graphics::Font,
     pub main_font: graphics::Font,
@@ -32,6 +41,15 @@ impl Assets {
             mul_ship: graphics::Image::new(ctx, "/mul-ship.png").unwrap(),
             crosshair: graphics::Image::new(ctx, "/crosshair.png").unwrap(),
             div_ship: graphics::Image::new(ctx, "/div-ship.png").unwrap(),
+            boss_ship: graphics::Image::new(ctx, "/boss-ship.png").unwrap(),
+            expression_ship: graphics::Image::new(ctx, "/expression-ship.png").unwrap(),
+            fraction_ship: graphics::Image::new(ctx, "/fraction-ship.png").unwrap(),
+            decimal_ship: graphics::Image::new(ctx, "/decimal-ship.png").unwrap(),
+            number_sense_ship: graphics::Image::new(ctx, "/number-sense-ship.png").unwrap(),
+            power_ship: graphics::Image::new(ctx, "/power-ship.png").unwrap(),
+            square_root_ship: graphics::Image::new(ctx, "/square-root-ship.png").unwrap(),
+            modulo_ship: graphics::Image::new(ctx, "/modulo-ship.png").unwrap(),
+            remainder_ship: graphics::Image::new(ctx, "/remainder-ship.png").unwrap(),
             title_font: graphics::Font::new(ctx, "/title.ttf").unwrap(),
             main_font: graphics::Font::new(ctx, "/main.ttf").unwrap(),
             number_font: graphics::Font::new(ctx, "/number.ttf").unwrap(),
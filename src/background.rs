@@ -89,13 +89,16 @@ impl Background {
     }
 }
 
-impl Scalable for Background {
+impl Positioned for Background {
     fn pct_pos(&self) -> na::Point2<f32> {
         na::Point2::new(0.0, 0.0)
     }
     fn pct_dimensions(&self) -> (f32, f32) {
         (1.0, 1.0)
     }
+}
+
+impl Scalable for Background {
     fn src_pixel_dimensions(&self) -> (f32, f32) {
         (self.src_pixel_width, self.src_pixel_height)
     }
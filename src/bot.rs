@@ -0,0 +1,286 @@
+use ggez::event::KeyCode;
+use rand::rngs::StdRng;
+use rand::*;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::alien::*;
+use crate::answer::*;
+use crate::difficulty::*;
+use crate::events::*;
+use crate::level::*;
+use crate::rational::*;
+use crate::simulation::*;
+
+// the bot gives up on a level after this much game time
+pub const MAX_BOT_MS: u64 = 20 * 60 * 1000;
+const BOT_STEP_MS: u64 = 16;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TargetStrategy {
+    // shoot whatever the game targets
+    Follow,
+    // always switch to the alien closest to the bottom
+    Lowest,
+    // switch to a random alien on screen
+    Random,
+}
+
+impl TargetStrategy {
+    pub fn from_name(name: &str) -> Option<TargetStrategy> {
+        match name.to_lowercase().as_str() {
+            "follow" => Some(TargetStrategy::Follow),
+            "lowest" => Some(TargetStrategy::Lowest),
+            "random" => Some(TargetStrategy::Random),
+            _ => None,
+        }
+    }
+}
+
+pub struct BotConfig {
+    // time between key presses, the answer and the Return each take one
+    pub ms_per_key: u64,
+    // chance of typing the right answer, 0.0 to 1.0
+    pub accuracy: f32,
+    pub strategy: TargetStrategy,
+    pub seed: u64,
+}
+
+impl Default for BotConfig {
+    fn default() -> BotConfig {
+        BotConfig {
+            ms_per_key: 400,
+            accuracy: 0.9,
+            strategy: TargetStrategy::Lowest,
+            seed: 0,
+        }
+    }
+}
+
+enum BotKey {
+    Char(char),
+    Key(KeyCode),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum WaveResult {
+    Survived,
+    LostLife,
+}
+
+/// One attempt at a wave, a wave the bot died on shows up once per life lost
+#[derive(Debug)]
+pub struct WaveReport {
+    pub wave: usize,
+    pub result: WaveResult,
+    pub lives: usize,
+}
+
+#[derive(Debug)]
+pub struct BotReport {
+    pub waves: Vec<WaveReport>,
+    pub beaten: bool,
+    pub lives: usize,
+    pub score: u32,
+    pub elapsed_ms: u64,
+}
+
+/// Plays a level through the Simulation's key and text input, the same
+/// paths the game feeds from the keyboard.
+pub struct Bot {
+    pub config: BotConfig,
+    rng: StdRng,
+    keys: VecDeque<BotKey>,
+    wait_ms: u64,
+    chosen: Option<usize>,
+}
+
+impl Bot {
+    pub fn new(config: BotConfig) -> Bot {
+        let rng = StdRng::seed_from_u64(config.seed);
+        Bot {
+            config,
+            rng,
+            keys: VecDeque::new(),
+            wait_ms: 0,
+            chosen: None,
+        }
+    }
+
+    pub fn play(
+        &mut self,
+        levels: Vec<Level>,
+        difficulties: Vec<Difficulty>,
+        level: usize,
+        difficulty: usize,
+    ) -> BotReport {
+        let mut sim = Simulation::new(levels, difficulties, self.config.seed);
+        sim.difficulty = difficulty;
+        sim.start_level(level);
+
+        let mut report = BotReport {
+            waves: Vec::new(),
+            beaten: false,
+            lives: sim.lives,
+            score: 0,
+            elapsed_ms: 0,
+        };
+        let mut wave = 0;
+        while report.elapsed_ms < MAX_BOT_MS {
+            self.step(&mut sim);
+            sim.update(Duration::from_millis(BOT_STEP_MS));
+            report.elapsed_ms += BOT_STEP_MS;
+
+            for event in sim.events.drain(..) {
+                match event {
+                    GameEvent::WaveCleared { .. } | GameEvent::LevelCompleted { .. } => {
+                        report.waves.push(WaveReport {
+                            wave,
+                            result: WaveResult::Survived,
+                            lives: sim.lives,
+                        });
+                        wave += 1;
+                    }
+                    GameEvent::LifeLost { .. } | GameEvent::GameOver => {
+                        report.waves.push(WaveReport {
+                            wave,
+                            result: WaveResult::LostLife,
+                            lives: sim.lives,
+                        });
+                        // the wave restarts with new aliens
+                        self.keys.clear();
+                        self.chosen = None;
+                    }
+                    _ => (),
+                }
+            }
+
+            match sim.state {
+                GameState::LevelComplete | GameState::Won => {
+                    report.beaten = true;
+                    break;
+                }
+                GameState::Dead => break,
+                _ => (),
+            }
+        }
+        report.lives = sim.lives;
+        report.score = sim.score.points;
+        report
+    }
+
+    // press at most one key, then wait ms_per_key before the next
+    fn step(&mut self, sim: &mut Simulation) {
+        if sim.state != GameState::Playing {
+            return;
+        }
+        if self.wait_ms > BOT_STEP_MS {
+            self.wait_ms -= BOT_STEP_MS;
+            return;
+        }
+        if self.keys.is_empty() {
+            self.plan(sim);
+        }
+        match self.keys.pop_front() {
+            Some(BotKey::Char(ch)) => sim.text_input(ch),
+            Some(BotKey::Key(keycode)) => sim.key_up(keycode),
+            None => return,
+        }
+        self.wait_ms = self.config.ms_per_key;
+    }
+
+    // decide the next keys to press: switch targets or type an answer
+    fn plan(&mut self, sim: &Simulation) {
+        let target = match sim.target {
+            Some(target) if is_shootable(&sim.aliens[target]) => target,
+            _ => return,
+        };
+        let wanted = match self.config.strategy {
+            TargetStrategy::Follow => target,
+            TargetStrategy::Lowest => get_lowest_living_alien(&sim.aliens).unwrap_or(target),
+            TargetStrategy::Random => match self.chosen {
+                Some(chosen) if is_shootable(&sim.aliens[chosen]) => chosen,
+                _ => {
+                    let on_screen: Vec<usize> = (0..sim.aliens.len())
+                        .filter(|i| is_shootable(&sim.aliens[*i]))
+                        .collect();
+                    on_screen[self.rng.gen_range(0, on_screen.len())]
+                }
+            },
+        };
+        self.chosen = Some(wanted);
+        if wanted != target && is_shootable(&sim.aliens[wanted]) {
+            self.keys.push_back(BotKey::Key(KeyCode::Right));
+            return;
+        }
+
+        let problem = &sim.aliens[target].problem;
+        let right = self.rng.gen::<f32>() < self.config.accuracy;
+        let typed = match problem.answer {
+            _ if right => problem.answer_text(),
+            Answer::Number(answer) => {
+                let miss = Rational::from(self.rng.gen_range(1, 10));
+                let typed = if self.rng.gen() {
+                    answer.checked_add(miss).unwrap_or(miss)
+                } else {
+                    answer.checked_sub(miss).unwrap_or(miss)
+                };
+                problem.format_answer(typed)
+            }
+            Answer::YesNo(yes) => Answer::YesNo(!yes).to_string(),
+            Answer::Remainder {
+                quotient,
+                remainder,
+            } => Answer::Remainder {
+                quotient: quotient + self.rng.gen_range(1, 10),
+                remainder,
+            }
+            .to_string(),
+        };
+        for ch in typed.chars() {
+            self.keys.push_back(BotKey::Char(ch));
+        }
+        self.keys.push_back(BotKey::Key(KeyCode::Return));
+        self.chosen = None;
+    }
+}
+
+// alive and on screen, so the turret can target it
+fn is_shootable(alien: &Alien) -> bool {
+    alien.state == AlienState::Alive && alien.pos[1] >= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(accuracy: f32) -> BotReport {
+        let mut bot = Bot::new(BotConfig {
+            accuracy,
+            seed: 1,
+            ..Default::default()
+        });
+        // addition-attack on the easiest tier
+        bot.play(Level::new(), Difficulty::new(), 0, 0)
+    }
+
+    #[test]
+    fn perfect_bot_clears_the_first_level() {
+        let lives = Difficulty::new()[0].lives;
+        let report = play(1.0);
+        assert!(report.beaten, "{:?}", report);
+        assert_eq!(report.lives, lives);
+        assert!(report
+            .waves
+            .iter()
+            .all(|wave| wave.result == WaveResult::Survived));
+    }
+
+    #[test]
+    fn hopeless_bot_loses() {
+        let report = play(0.0);
+        assert!(!report.beaten, "{:?}", report);
+        assert_eq!(report.lives, 0);
+        assert!(report.elapsed_ms < MAX_BOT_MS);
+    }
+}
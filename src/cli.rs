@@ -0,0 +1,248 @@
+use crate::analytics::*;
+use crate::bot::*;
+use crate::difficulty::*;
+use crate::level::*;
+use crate::pack::*;
+use crate::profile::*;
+
+use std::path::Path;
+
+/// Runs a command line tool instead of the game, these don't need ggez.
+/// Returns the exit code, or None to start the game normally.
+pub fn run(args: &[String]) -> Option<i32> {
+    match args.get(1).map(|arg| arg.as_str()) {
+        Some("--validate") => Some(validate(args)),
+        Some("--export") => Some(export(args)),
+        Some("--bot") => Some(bot(args)),
+        _ => None,
+    }
+}
+
+// mathdefense --validate [levels.json] [difficulties.json], also checks every pack in PACK_DIR
+fn validate(args: &[String]) -> i32 {
+    let mut code = 0;
+    // the game falls back to the built in difficulties without a file
+    let file = args.get(3).map(|s| s.as_str()).unwrap_or(DIFFICULTY_FILE);
+    let difficulties = if args.len() > 3 || Path::new(file).exists() {
+        match Difficulty::validate_file(Path::new(file)) {
+            Ok(difficulties) => {
+                println!("{} is valid, {} difficulties", file, difficulties.len());
+                difficulties
+            }
+            Err(errors) => {
+                for error in &errors {
+                    println!("{}", error);
+                }
+                code = 1;
+                Difficulty::new()
+            }
+        }
+    } else {
+        Difficulty::new()
+    };
+    let file = args.get(2).map(|s| s.as_str()).unwrap_or(LEVEL_FILE);
+    let levels = Level::validate_file(Path::new(file))
+        .and_then(|levels| known_tiers(&levels, &difficulties).map(|_| levels));
+    match levels {
+        Ok(levels) => println!("{} is valid, {} levels", file, levels.len()),
+        Err(errors) => {
+            for error in &errors {
+                println!("{}", error);
+            }
+            code = 1;
+        }
+    }
+    for path in Pack::pack_files() {
+        let pack = Pack::validate_file(&path)
+            .and_then(|pack| known_tiers(&pack.levels, &difficulties).map(|_| pack));
+        match pack {
+            Ok(pack) => println!("{} is valid, {} levels", path.display(), pack.levels.len()),
+            Err(errors) => {
+                println!("{} has errors", path.display());
+                for error in &errors {
+                    println!("  {}", error);
+                }
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+// levels can only be checked against the difficulty file once both are read
+fn known_tiers(levels: &[Level], difficulties: &[Difficulty]) -> Result<(), Vec<ValidationError>> {
+    let errors = Level::validate_tiers(levels, difficulties);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+// mathdefense --export <profile name> <file.csv|file.json>
+fn export(args: &[String]) -> i32 {
+    if args.len() < 4 {
+        println!("usage: --export <profile name> <file.csv|file.json>");
+        return 1;
+    }
+    let profile = match Profile::load_all()
+        .into_iter()
+        .find(|profile| slugify(&profile.name) == slugify(&args[2]))
+    {
+        Some(profile) => profile,
+        None => {
+            println!("no profile named {}", args[2]);
+            return 1;
+        }
+    };
+    let records = load_records(&profile.dir());
+    let out = Path::new(&args[3]);
+    let result = if args[3].ends_with(".json") {
+        export_json(&records, out)
+    } else {
+        export_csv(&records, out)
+    };
+    match result {
+        Ok(_) => {
+            println!("wrote {} answers to {}", records.len(), args[3]);
+            for fact in summarize(&records).iter().take(5) {
+                println!(
+                    "  {}: {} of {} correct, {} wrong answers, {}ms average",
+                    fact.problem,
+                    fact.correct,
+                    fact.seen,
+                    fact.wrong_attempts,
+                    fact.average_time_ms
+                );
+            }
+            0
+        }
+        Err(msg) => {
+            println!("{}", msg);
+            1
+        }
+    }
+}
+
+// mathdefense --bot <level id|pack id|all> <difficulty> [ms per key] [accuracy] [strategy]
+fn bot(args: &[String]) -> i32 {
+    let usage = "usage: --bot <level id|pack id|all> <difficulty> [ms per key] [accuracy] [follow|lowest|random]";
+    if args.len() < 4 {
+        println!("{}", usage);
+        return 1;
+    }
+    let levels = match Level::validate_file(Path::new(LEVEL_FILE)) {
+        Ok(levels) => levels,
+        Err(errors) => {
+            for error in &errors {
+                println!("{}", error);
+            }
+            return 1;
+        }
+    };
+    // a whole pack, or one level, pack level ids look like times-tables/tables-to-ten
+    let packs = Pack::load_all(levels);
+    let (levels, level_indices): (Vec<Level>, Vec<usize>) = if args[2] == "all" {
+        (packs[0].levels.clone(), (0..packs[0].levels.len()).collect())
+    } else if let Some(pack) = packs.iter().find(|pack| pack.id() == args[2]) {
+        (pack.levels.clone(), (0..pack.levels.len()).collect())
+    } else {
+        match packs.iter().find_map(|pack| {
+            let index = pack.levels.iter().position(|level| level.id() == args[2])?;
+            Some((pack.levels.clone(), index))
+        }) {
+            Some((levels, index)) => (levels, vec![index]),
+            None => {
+                println!("no level or pack with id {}", args[2]);
+                return 1;
+            }
+        }
+    };
+    let difficulties = match Difficulty::load_or_default() {
+        Ok(difficulties) => difficulties,
+        Err(errors) => {
+            for error in &errors {
+                println!("{}", error);
+            }
+            return 1;
+        }
+    };
+    // difficulty by name or number, "Space Marine" or 3
+    let difficulty = match difficulties
+        .iter()
+        .position(|d| d.name.to_lowercase() == args[3].to_lowercase())
+        .or_else(|| args[3].parse::<usize>().ok())
+    {
+        Some(difficulty) if difficulty < difficulties.len() => difficulty,
+        _ => {
+            let names: Vec<&str> = difficulties.iter().map(|d| d.name.as_str()).collect();
+            println!("difficulty must be one of {}", names.join(", "));
+            return 1;
+        }
+    };
+    let mut config = BotConfig::default();
+    if let Some(ms) = args.get(4) {
+        match ms.parse::<u64>() {
+            Ok(ms) => config.ms_per_key = ms,
+            Err(_) => {
+                println!("{}", usage);
+                return 1;
+            }
+        }
+    }
+    if let Some(accuracy) = args.get(5) {
+        match accuracy.parse::<f32>() {
+            Ok(accuracy) if (0.0..=1.0).contains(&accuracy) => config.accuracy = accuracy,
+            _ => {
+                println!("accuracy must be between 0 and 1");
+                return 1;
+            }
+        }
+    }
+    if let Some(strategy) = args.get(6) {
+        match TargetStrategy::from_name(strategy) {
+            Some(strategy) => config.strategy = strategy,
+            None => {
+                println!("{}", usage);
+                return 1;
+            }
+        }
+    }
+
+    println!(
+        "{} at {}ms per key, {}% accuracy, {:?} targeting",
+        difficulties[difficulty].name,
+        config.ms_per_key,
+        (config.accuracy * 100.0) as i32,
+        config.strategy
+    );
+    let mut bot = Bot::new(config);
+    let mut all_beaten = true;
+    for index in level_indices {
+        let report = bot.play(levels.clone(), difficulties.clone(), index, difficulty);
+        println!("{}", levels[index].title);
+        for wave in &report.waves {
+            let result = match wave.result {
+                WaveResult::Survived => "survived",
+                WaveResult::LostLife => "lost a life",
+            };
+            println!("  wave {}: {}, {} lives left", wave.wave + 1, result, wave.lives);
+        }
+        if report.beaten {
+            println!(
+                "  beaten with {} lives left, scored {}",
+                report.lives, report.score
+            );
+        } else if report.elapsed_ms >= MAX_BOT_MS {
+            println!("  gave up after {} minutes", MAX_BOT_MS / 60000);
+        } else {
+            println!("  died");
+        }
+        all_beaten &= report.beaten;
+    }
+    if all_beaten {
+        0
+    } else {
+        1
+    }
+}
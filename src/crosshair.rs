@@ -32,13 +32,16 @@ impl Crosshair {
         let _ = graphics::draw(ctx, &assets.crosshair, crosshair_params);
     }
 }
-impl Scalable for Crosshair {
+impl Positioned for Crosshair {
     fn pct_pos(&self) -> na::Point2<f32> {
         na::Point2::new(0.0, 0.0)
     }
     fn pct_dimensions(&self) -> (f32, f32) {
         (0.090, 0.125)
     }
+}
+
+impl Scalable for Crosshair {
     fn src_pixel_dimensions(&self) -> (f32, f32) {
         (self.src_pixel_width, self.src_pixel_height)
     }
@@ -0,0 +1,95 @@
+use crate::level::*;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Reads a json data file and checks it with `validate`. Missing files and
+/// syntax errors come back as ValidationErrors too, so they print the same.
+pub fn read_validated<T, V>(path: &Path, validate: V) -> Result<T, Vec<ValidationError>>
+where
+    T: DeserializeOwned,
+    V: FnOnce(&T) -> Vec<ValidationError>,
+{
+    let file_error =
+        |e: std::io::Error| vec![ValidationError::new(path.display().to_string(), e.to_string())];
+    let mut file = File::open(path).map_err(file_error)?;
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer).map_err(file_error)?;
+    let data: T =
+        serde_json::from_slice(&buffer[..]).map_err(|e| vec![ValidationError::from_json(&e)])?;
+    let errors = validate(&data);
+    if errors.is_empty() {
+        Ok(data)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Loads `file`, creating it from `defaults` when there isn't one. A file
+/// with errors is never overwritten, the author will want to fix it, so
+/// the errors are printed, it is backed up to .bak and the defaults used.
+/// `kind` names the file in messages, like "level".
+pub fn load_or_create<T, V, D>(file: &str, kind: &str, validate: V, defaults: D) -> T
+where
+    T: Serialize + DeserializeOwned,
+    V: Fn(&T) -> Vec<ValidationError>,
+    D: Fn() -> T,
+{
+    let path = Path::new(file);
+    if !path.exists() {
+        println!("No {} file found, creating {}", kind, file);
+        let data = defaults();
+        save(path, kind, &data, validate);
+        return data;
+    }
+
+    match read_validated(path, &validate) {
+        Ok(data) => data,
+        Err(errors) => {
+            println!("Error loading {} file {}.\nUsing the defaults", kind, file);
+            for error in &errors {
+                println!("  {}", error);
+            }
+            // in case something else clobbers it
+            let backup = file.to_string() + ".bak";
+            match fs::copy(path, &backup) {
+                Ok(_) => println!("Backed up {} file to {}", kind, backup),
+                Err(e) => println!("Couldn't back up {} file to {}\n{}", kind, backup, e),
+            }
+            defaults()
+        }
+    }
+}
+
+/// Writes `data` to `path` as pretty json, making its directory if need
+/// be, unless the file is already there and has errors
+pub fn save<T, V>(path: &Path, kind: &str, data: &T, validate: V)
+where
+    T: Serialize + DeserializeOwned,
+    V: Fn(&T) -> Vec<ValidationError>,
+{
+    fn save_helper<T: Serialize>(path: &Path, kind: &str, data: &T) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("couldn't create {}\n{}", dir.display(), e))?;
+        }
+        let serialized = serde_json::to_string_pretty(data)
+            .map_err(|_| format!("couldn't serialize {} file", kind))?;
+        let mut file =
+            File::create(path).map_err(|_| format!("couldn't create {}", path.display()))?;
+        file.write_all(serialized.as_bytes())
+            .map_err(|_| format!("couldn't write to {} file", kind))?;
+        Ok(())
+    }
+    // don't replace a hand edited file that failed to load with the defaults
+    if path.exists() && read_validated(path, &validate).is_err() {
+        println!("Not saving, {} has errors", path.display());
+        return;
+    }
+    if let Err(msg) = save_helper(path, kind, data) {
+        println!("{}", msg);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::data_file::*;
+use crate::level::*;
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const DIFFICULTY_FILE: &str = "resources/difficulties.json";
+// spare turrets are drawn in the corner, and with many more than this
+// a level can't really be lost
+pub const MAX_LIVES: usize = 9;
+
+/// One difficulty tier, like Rookie. The multipliers scale every
+/// WaveGroup's speed, number range and ship count. Tiers are listed
+/// easiest first, and progress and high scores are kept by name.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Difficulty {
+    pub name: String,
+    pub speed: f32,
+    pub min_number: f32,
+    pub max_number: f32,
+    pub num_ships: f32,
+    // used by groups that don't set their own negative_answers
+    #[serde(default)]
+    pub negative_answers: bool,
+    // spare turrets for the campaign and endless mode, from 1 to MAX_LIVES
+    pub lives: usize,
+}
+
+// Plays a level the way it was written, practice uses this so it sticks
+// to the numbers the player chose
+impl Default for Difficulty {
+    fn default() -> Difficulty {
+        Difficulty {
+            name: "Normal".to_string(),
+            speed: 1.0,
+            min_number: 1.0,
+            max_number: 1.0,
+            num_ships: 1.0,
+            negative_answers: false,
+            lives: 2,
+        }
+    }
+}
+
+impl Difficulty {
+    fn tier(name: &str, speed: f32, numbers: f32, negative_answers: bool) -> Difficulty {
+        Difficulty {
+            name: name.to_string(),
+            speed,
+            min_number: numbers,
+            max_number: numbers,
+            num_ships: numbers,
+            negative_answers,
+            lives: 2,
+        }
+    }
+
+    pub fn new() -> Vec<Difficulty> {
+        vec![
+            Difficulty::tier("Rookie", 1.0, 1.0, false),
+            Difficulty::tier("Cadet", 1.1, 1.25, false),
+            Difficulty::tier("Veteran", 1.25, 2.0, true),
+            Difficulty::tier("Space Marine", 1.5, 3.0, true),
+        ]
+    }
+
+    /// Returns a list of reasons this tier can't be played
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if self.name.trim().is_empty() {
+            errors.push(ValidationError::new("name", "name is empty".to_string()));
+        }
+        let multipliers = [
+            ("speed", self.speed),
+            ("max_number", self.max_number),
+            ("num_ships", self.num_ships),
+        ];
+        for (path, multiplier) in multipliers.iter() {
+            // NaN isn't positive either
+            if *multiplier <= 0.0 || multiplier.is_nan() {
+                errors.push(ValidationError::new(
+                    *path,
+                    format!("{} ({}) must be positive", path, multiplier),
+                ));
+            }
+        }
+        if self.min_number < 0.0 || self.min_number.is_nan() {
+            errors.push(ValidationError::new(
+                "min_number",
+                format!("min_number ({}) can't be negative", self.min_number),
+            ));
+        }
+        if !(1..=MAX_LIVES).contains(&self.lives) {
+            errors.push(ValidationError::new(
+                "lives",
+                format!("lives ({}) must be from 1 to {}", self.lives, MAX_LIVES),
+            ));
+        }
+        errors
+    }
+
+    pub fn validate_difficulties(difficulties: &[Difficulty]) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if difficulties.is_empty() {
+            errors.push(ValidationError::new(
+                "difficulties",
+                "there are no difficulties".to_string(),
+            ));
+        }
+        for (i, difficulty) in difficulties.iter().enumerate() {
+            let path = format!("difficulties[{}]", i);
+            if let Some(first) = difficulties[..i]
+                .iter()
+                .position(|other| other.name == difficulty.name)
+            {
+                errors.push(ValidationError::new(
+                    path.clone() + ".name",
+                    format!(
+                        "name \"{}\" is already used by difficulties[{}]",
+                        difficulty.name, first
+                    ),
+                ));
+            }
+            errors.extend(difficulty.validate().into_iter().map(|e| e.prefixed(&path)));
+        }
+        errors
+    }
+
+    /// Parses and validates a difficulty file without needing a ggez context
+    pub fn validate_file(path: &Path) -> Result<Vec<Difficulty>, Vec<ValidationError>> {
+        read_validated(path, |difficulties: &Vec<Difficulty>| {
+            Difficulty::validate_difficulties(difficulties)
+        })
+    }
+
+    // the file if there is one, the built in tiers otherwise, never writes
+    pub fn load_or_default() -> Result<Vec<Difficulty>, Vec<ValidationError>> {
+        let path = Path::new(DIFFICULTY_FILE);
+        if path.exists() {
+            Difficulty::validate_file(path)
+        } else {
+            Ok(Difficulty::new())
+        }
+    }
+
+    pub fn load_from_file() -> Vec<Difficulty> {
+        let validate = |difficulties: &Vec<Difficulty>| {
+            Difficulty::validate_difficulties(difficulties)
+        };
+        load_or_create(DIFFICULTY_FILE, "difficulty", validate, Difficulty::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    fn paths(json: &str) -> Vec<String> {
+        let difficulties: Vec<Difficulty> = serde_json::from_str(json).unwrap();
+        Difficulty::validate_difficulties(&difficulties)
+            .into_iter()
+            .map(|error| error.path)
+            .collect()
+    }
+
+    fn tier(name: &str, speed: f32, max_number: f32, lives: usize) -> String {
+        format!(
+            r#"{{"name": "{}", "speed": {}, "min_number": 1.0, "max_number": {},
+                "num_ships": 1.0, "lives": {}}}"#,
+            name, speed, max_number, lives
+        )
+    }
+
+    #[test]
+    fn built_in_tiers_are_valid() {
+        assert!(Difficulty::validate_difficulties(&Difficulty::new()).is_empty());
+        assert!(Difficulty::validate_difficulties(&[Difficulty::default()]).is_empty());
+    }
+
+    #[test]
+    fn bad_tiers_are_rejected() {
+        assert_eq!(paths("[]"), vec!["difficulties"]);
+        let json = format!(
+            "[{}, {}, {}, {}, {}]",
+            tier("Kindergarten", 0.5, 0.5, 3),
+            tier("Kindergarten", 1.0, 1.0, 2),
+            tier("Rookie", 0.0, -1.0, 2),
+            tier("Cadet", 1.0, 1.0, 0),
+            tier("Veteran", 1.0, 1.0, MAX_LIVES + 1),
+        );
+        assert_eq!(
+            paths(&json),
+            vec![
+                "difficulties[1].name",
+                "difficulties[2].speed",
+                "difficulties[2].max_number",
+                "difficulties[3].lives",
+                "difficulties[4].lives",
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_files_are_rejected() {
+        let file = format!("mathdefense-difficulties-{}.json", process::id());
+        let path = env::temp_dir().join(file);
+        fs::write(&path, format!("[{}]", tier("Kindergarten", 0.5, 0.5, 3))).unwrap();
+        let good = Difficulty::validate_file(&path);
+        fs::write(&path, format!("[{}]", tier("Kindergarten", -0.5, 0.5, 3))).unwrap();
+        let bad = Difficulty::validate_file(&path);
+        fs::write(&path, "[{\"name\": \"Rookie\"").unwrap();
+        let broken = Difficulty::validate_file(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(good.unwrap()[0].name, "Kindergarten");
+        assert_eq!(bad.unwrap_err().len(), 1);
+        assert_eq!(broken.unwrap_err().len(), 1);
+    }
+}
@@ -0,0 +1,105 @@
+use rand::*;
+
+use crate::level::*;
+
+pub const ENDLESS_ID: &str = "endless";
+pub const ENDLESS_MAX_SPEED: f32 = 9.0;
+pub const ENDLESS_MAX_SHIPS: usize = 16;
+
+// new operations join the mix every couple of waves
+fn operations_for(wave: usize) -> &'static [Operation] {
+    const ALL: [Operation; 4] = [
+        Operation::Add,
+        Operation::Subtract,
+        Operation::Multiply,
+        Operation::Divide,
+    ];
+    &ALL[..(1 + wave / 2).min(ALL.len())]
+}
+
+/// The wave after `wave` waves survived, each one faster, bigger and
+/// with larger numbers than the last
+pub fn endless_wave<R: Rng>(wave: usize, rng: &mut R) -> Wave {
+    let operations = operations_for(wave);
+    let group_count = rng.gen_range(1, operations.len().min(3) + 1);
+    let ships = (4 + wave / 2).min(ENDLESS_MAX_SHIPS);
+    let speed = (2.5 + 0.3 * wave as f32).min(ENDLESS_MAX_SPEED);
+
+    let groups = (0..group_count)
+        .map(|_| {
+            let operation = operations[rng.gen_range(0, operations.len())];
+            // multiplying and dividing get hard a lot faster than adding
+            let max_number = match operation {
+                Operation::Add | Operation::Subtract => (6 + 2 * wave as i32).min(50),
+                _ => (4 + wave as i32 / 2).min(13),
+            };
+            WaveGroup {
+                operation,
+                speed,
+                num_ships: (ships / group_count).max(1),
+                min_number: 0,
+                max_number,
+                ..Default::default()
+            }
+        })
+        .collect();
+    Wave { groups }
+}
+
+/// Starts with a single wave, the Simulation adds one each time a wave is cleared
+pub fn endless_level<R: Rng>(rng: &mut R) -> Level {
+    Level {
+        id: ENDLESS_ID.to_string(),
+        waves: vec![endless_wave(0, rng)],
+        background_file: String::new(),
+        title: "Endless".to_string(),
+        unlocked: Unlocked::Everywhere(true),
+        penalty: PenaltyMode::None,
+        reveal_after: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    const WAVES: usize = 100;
+
+    fn max_number(wave: &Wave, operation: Operation) -> Option<i32> {
+        wave.groups
+            .iter()
+            .filter(|group| group.operation == operation)
+            .map(|group| group.max_number)
+            .max()
+    }
+
+    #[test]
+    fn escalation_is_monotonic_and_capped() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let waves: Vec<Wave> = (0..WAVES).map(|wave| endless_wave(wave, &mut rng)).collect();
+        for (i, wave) in waves.iter().enumerate() {
+            let ships: usize = wave.groups.iter().map(|group| group.num_ships).sum();
+            assert!(ships <= ENDLESS_MAX_SHIPS);
+            for group in &wave.groups {
+                assert!(group.validate().is_empty(), "{:?}", group.validate());
+                assert!(group.speed <= ENDLESS_MAX_SPEED);
+            }
+            assert!(operations_for(i).len() <= operations_for(i + 1).len());
+            let next = match waves.get(i + 1) {
+                Some(next) => next,
+                None => continue,
+            };
+            assert!(next.groups[0].speed >= wave.groups[0].speed);
+            for operation in operations_for(WAVES).iter() {
+                if let (Some(a), Some(b)) =
+                    (max_number(wave, *operation), max_number(next, *operation))
+                {
+                    assert!(b >= a, "{:?} {} then {}", operation, a, b);
+                }
+            }
+        }
+        assert_eq!(waves[WAVES - 1].groups[0].speed, ENDLESS_MAX_SPEED);
+        assert_eq!(operations_for(WAVES).len(), 4);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::analytics::*;
+use crate::practice::*;
+
+/// Everything that happens in a game, emitted by the Simulation and handed
+/// to each listener in turn. Levels and waves are indices into the
+/// simulation's levels.
+#[derive(Debug, Clone)]
+pub enum GameEvent {
+    // a level begins, from the menu or after warping in
+    LevelStarted { level: usize },
+    AlienDestroyed(AnswerRecord),
+    // a right answer that didn't finish off a boss
+    BossHit(AnswerRecord),
+    AlienReachedBottom(AnswerRecord),
+    WrongAnswer,
+    // a wave other than the last one in the level
+    WaveCleared { wave: usize },
+    LevelCompleted {
+        level: usize,
+        unlocked: Option<usize>,
+        score: u32,
+    },
+    // the player left the level complete screen for the next level
+    WarpStarted { level: usize },
+    // the wave restarts with lives_left spare turrets
+    LifeLost { wave: usize, lives_left: usize },
+    GameOver,
+    GameWon,
+    PracticeStarted,
+    PracticeFinished(PracticeSummary),
+    MadMinuteStarted,
+    // time ran out, or the player left early
+    MadMinuteFinished(PracticeSummary),
+    EndlessStarted,
+    // waves survived and millis played in the run
+    EndlessFinished { waves: usize, time_ms: u64, score: u32 },
+}
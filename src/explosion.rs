@@ -36,7 +36,7 @@ impl Explosion {
         )
     }
 
-    pub fn update(&mut self, _ctx: &mut Context, dt: std::time::Duration) {
+    pub fn update(&mut self, dt: std::time::Duration) {
         if self.elapsed - self.start_time <= self.duration {
             self.elapsed += dt.as_millis() as f32;
             if self.elapsed >= self.start_time {
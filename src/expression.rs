@@ -0,0 +1,233 @@
+use rand::*;
+
+use crate::level::*;
+use crate::rational::*;
+
+// how deep expressions go when a WaveGroup doesn't say
+pub const DEFAULT_EXPRESSION_DEPTH: usize = 2;
+pub const MAX_EXPRESSION_DEPTH: usize = 4;
+pub const DEFAULT_EXPRESSION_OPERATIONS: [Operation; 3] =
+    [Operation::Add, Operation::Subtract, Operation::Multiply];
+
+/// A tree of numbers and operations, like (3+4)X2 or 10-2X3. Fraction
+/// and Decimal problems are a single operation on two fractions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(Rational),
+    Binary(Box<Expr>, Operation, Box<Expr>),
+}
+
+pub fn symbol(operation: Operation) -> &'static str {
+    match operation {
+        Operation::Add => "+",
+        Operation::Subtract => "-",
+        Operation::Multiply => "X",
+        Operation::Divide => "/",
+        _ => "?",
+    }
+}
+
+/// One number of a problem, in parentheses when it is negative and
+/// something comes before it, so 3-(-5) rather than 3--5
+pub fn number_text(n: Rational, decimals: bool, first: bool) -> String {
+    let text = if decimals {
+        n.to_decimal().unwrap_or_else(|| n.to_string())
+    } else {
+        n.to_string()
+    };
+    if n.is_negative() && !first {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+// multiply and divide bind tighter than add and subtract
+fn precedence(operation: Operation) -> u32 {
+    match operation {
+        Operation::Multiply | Operation::Divide => 2,
+        _ => 1,
+    }
+}
+
+impl Expr {
+    pub fn binary(left: Expr, operation: Operation, right: Expr) -> Expr {
+        Expr::Binary(Box::new(left), operation, Box::new(right))
+    }
+
+    /// The exact value with the usual precedence, None if it divides by
+    /// zero or overflows
+    pub fn eval(&self) -> Option<Rational> {
+        match self {
+            Expr::Number(n) => Some(*n),
+            Expr::Binary(left, operation, right) => {
+                let (a, b) = (left.eval()?, right.eval()?);
+                match operation {
+                    Operation::Add => a.checked_add(b),
+                    Operation::Subtract => a.checked_sub(b),
+                    Operation::Multiply => a.checked_mul(b),
+                    Operation::Divide => a.checked_div(b),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// True when no step along the way goes below zero
+    pub fn is_natural(&self) -> bool {
+        match self {
+            Expr::Number(n) => !n.is_negative(),
+            Expr::Binary(left, _, right) => {
+                left.is_natural()
+                    && right.is_natural()
+                    && self.eval().is_some_and(|n| !n.is_negative())
+            }
+        }
+    }
+
+    // every number in the expression, left to right
+    pub fn operands(&self) -> Vec<Rational> {
+        match self {
+            Expr::Number(n) => vec![*n],
+            Expr::Binary(left, _, right) => {
+                let mut operands = left.operands();
+                operands.extend(right.operands());
+                operands
+            }
+        }
+    }
+
+    /// Written out with only the parentheses precedence needs, numbers
+    /// as decimals or as fractions like 3/4. Negative numbers get their
+    /// own parentheses unless they come first: -3-(-5).
+    pub fn to_text(&self, decimals: bool) -> String {
+        self.text(decimals, true)
+    }
+
+    fn text(&self, decimals: bool, first: bool) -> String {
+        match self {
+            Expr::Number(n) => number_text(*n, decimals, first),
+            Expr::Binary(left, operation, right) => {
+                let left_text = match **left {
+                    Expr::Binary(_, inner, _) if precedence(inner) < precedence(*operation) => {
+                        format!("({})", left.text(decimals, true))
+                    }
+                    _ => left.text(decimals, first),
+                };
+                // a-(b-c) and a/(b/c) can't lose theirs either
+                let right_text = match **right {
+                    Expr::Binary(_, inner, _)
+                        if precedence(inner) < precedence(*operation)
+                            || (precedence(inner) == precedence(*operation)
+                                && (*operation == Operation::Subtract
+                                    || *operation == Operation::Divide)) =>
+                    {
+                        format!("({})", right.text(decimals, true))
+                    }
+                    _ => right.text(decimals, false),
+                };
+                left_text + symbol(*operation) + &right_text
+            }
+        }
+    }
+
+    /// A random expression with numbers from min..max. One side of every
+    /// operation goes the full depth, so there are at least depth + 1
+    /// numbers. Division is built backwards from the divisor, which
+    /// always takes the full depth, so it can come out even.
+    pub fn generate<R: Rng>(
+        rng: &mut R,
+        depth: usize,
+        operations: &[Operation],
+        min: i32,
+        max: i32,
+    ) -> Expr {
+        if depth == 0 {
+            return Expr::Number(Rational::from(rng.gen_range(min, max)));
+        }
+        let operation = operations[rng.gen_range(0, operations.len())];
+        let (left_depth, right_depth) = if operation == Operation::Divide {
+            (0, depth - 1)
+        } else if rng.gen() {
+            (depth - 1, rng.gen_range(0, depth))
+        } else {
+            (rng.gen_range(0, depth), depth - 1)
+        };
+        let right = Expr::generate(rng, right_depth, operations, min, max);
+        let quotient = Rational::from(rng.gen_range(min, max));
+        let left = match (operation, right.eval()) {
+            (Operation::Divide, Some(divisor)) if divisor != Rational::zero() => {
+                match divisor.checked_mul(quotient) {
+                    Some(dividend) => Expr::Number(dividend),
+                    None => Expr::generate(rng, left_depth, operations, min, max),
+                }
+            }
+            _ => Expr::generate(rng, left_depth, operations, min, max),
+        };
+        Expr::binary(left, operation, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    fn number(n: i32) -> Expr {
+        Expr::Number(Rational::from(n))
+    }
+
+    #[test]
+    fn precedence_and_parentheses() {
+        let expression = Expr::binary(
+            number(10),
+            Operation::Subtract,
+            Expr::binary(number(2), Operation::Multiply, number(3)),
+        );
+        assert_eq!(expression.to_text(false), "10-2X3");
+        assert_eq!(expression.eval(), Some(Rational::from(4)));
+
+        let expression = Expr::binary(
+            Expr::binary(number(3), Operation::Add, number(4)),
+            Operation::Multiply,
+            number(2),
+        );
+        assert_eq!(expression.to_text(false), "(3+4)X2");
+        assert_eq!(expression.eval(), Some(Rational::from(14)));
+
+        let expression = Expr::binary(
+            number(9),
+            Operation::Subtract,
+            Expr::binary(number(2), Operation::Add, number(3)),
+        );
+        assert_eq!(expression.to_text(false), "9-(2+3)");
+        assert_eq!(expression.eval(), Some(Rational::from(4)));
+
+        let expression = Expr::binary(number(-3), Operation::Subtract, number(-5));
+        assert_eq!(expression.to_text(false), "-3-(-5)");
+        assert_eq!(expression.eval(), Some(Rational::from(2)));
+    }
+
+    #[test]
+    fn generated_expressions_go_the_full_depth() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let all = [
+            Operation::Add,
+            Operation::Subtract,
+            Operation::Multiply,
+            Operation::Divide,
+        ];
+        for operations in [&all[..], &all[3..]].iter() {
+            for depth in 1..=MAX_EXPRESSION_DEPTH {
+                for _ in 0..50 {
+                    let expression = Expr::generate(&mut rng, depth, operations, 1, 10);
+                    assert!(
+                        expression.operands().len() > depth,
+                        "{}",
+                        expression.to_text(false)
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use rand::*;
+use serde::{Deserialize, Serialize};
+
+use crate::level::*;
+use crate::mastery::*;
+use crate::problem::*;
+use crate::rational::*;
+
+/// One problem a WaveGroup asks exactly, like [8, 7] for 8X7. Written as
+/// just the operands, or as {"operands": [7, 7], "weight": 3} to come up
+/// three times as often when shuffled.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(from = "SavedFact", into = "SavedFact")]
+pub struct Fact {
+    pub operands: Vec<i32>,
+    pub weight: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum SavedFact {
+    Operands(Vec<i32>),
+    Weighted {
+        operands: Vec<i32>,
+        #[serde(default = "default_weight")]
+        weight: u32,
+    },
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl From<SavedFact> for Fact {
+    fn from(saved: SavedFact) -> Fact {
+        match saved {
+            SavedFact::Operands(operands) => Fact {
+                operands,
+                weight: default_weight(),
+            },
+            SavedFact::Weighted { operands, weight } => Fact {
+                operands,
+                weight,
+            },
+        }
+    }
+}
+
+// saved as just the operands unless it has a weight
+impl From<Fact> for SavedFact {
+    fn from(fact: Fact) -> SavedFact {
+        if fact.weight == default_weight() {
+            SavedFact::Operands(fact.operands)
+        } else {
+            SavedFact::Weighted {
+                operands: fact.operands,
+                weight: fact.weight,
+            }
+        }
+    }
+}
+
+/// Builds the problem a fact asks, None if the numbers don't make one,
+/// like 7/0 or the square root of 50
+pub fn fact_problem(operation: Operation, numbers: &[i32]) -> Option<Problem> {
+    if operation.is_basic() {
+        return match numbers {
+            [a, b] => Problem::new(operation, *a, *b).ok(),
+            _ => None,
+        };
+    }
+    let fits = matches!(
+        (operation, numbers),
+        (Operation::SquareRoot, [_])
+            | (Operation::Prime, [_])
+            | (Operation::Power, [_, _])
+            | (Operation::Modulo, [_, _])
+            | (Operation::DivideWithRemainder, [_, _])
+            | (Operation::Compare, [_, _])
+            | (Operation::Round, [_, _])
+            | (Operation::PlaceValue, [_, _])
+    );
+    if !fits {
+        return None;
+    }
+    let problem = if operation.is_number_sense() {
+        Problem::number_sense(operation, numbers.iter().map(|n| Rational::from(*n)).collect())
+    } else {
+        Problem::integer(operation, numbers)
+    };
+    Some(problem).filter(|problem| problem.is_valid())
+}
+
+impl WaveGroup {
+    pub fn facts(&self) -> &[Fact] {
+        self.facts.as_ref().map_or(&[], |facts| &facts[..])
+    }
+
+    pub fn validate_facts(&self) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let facts = match &self.facts {
+            Some(facts) => facts,
+            None => {
+                if self.shuffle.is_some() || self.no_repeat.is_some() {
+                    errors.push(ValidationError::new(
+                        "facts",
+                        "shuffle and no_repeat need a list of facts".to_string(),
+                    ));
+                }
+                return errors;
+            }
+        };
+        if facts.is_empty() {
+            errors.push(ValidationError::new("facts", "facts is empty".to_string()));
+        }
+        let mixed = [Operation::Expression, Operation::Fraction, Operation::Decimal];
+        if mixed.contains(&self.operation) {
+            errors.push(ValidationError::new(
+                "facts",
+                format!("{:?} problems can't list facts", self.operation),
+            ));
+            return errors;
+        }
+        if self.first_sign.is_some() || self.second_sign.is_some() {
+            errors.push(ValidationError::new(
+                "facts",
+                "facts keep the signs they are written with".to_string(),
+            ));
+        }
+        for (i, fact) in facts.iter().enumerate() {
+            let path = format!("facts[{}]", i);
+            if fact_problem(self.operation, &fact.operands).is_none() {
+                errors.push(ValidationError::new(
+                    path.clone(),
+                    format!("{:?} isn't a {:?} problem", fact.operands, self.operation),
+                ));
+            }
+            if fact.weight < 1 {
+                errors.push(ValidationError::new(
+                    path.clone() + ".weight",
+                    "weight must be at least 1".to_string(),
+                ));
+            }
+            let same = facts[..i].iter().position(|other| other.operands == fact.operands);
+            if let Some(first) = same {
+                errors.push(ValidationError::new(
+                    path,
+                    format!("same as facts[{}], give that one a weight instead", first),
+                ));
+            }
+        }
+        if let Some(no_repeat) = self.no_repeat {
+            // there has to be a fact left to pick
+            if no_repeat >= facts.len() {
+                errors.push(ValidationError::new(
+                    "no_repeat",
+                    format!(
+                        "no_repeat ({}) must be less than the number of facts ({})",
+                        no_repeat,
+                        facts.len()
+                    ),
+                ));
+            }
+        }
+        errors
+    }
+}
+
+impl<R: Rng> ProblemGenerator<R> {
+    /// The group's next fact, in the order listed or picked at random by
+    /// weight. Adaptive mode also favors the shuffled facts the player
+    /// has trouble with. None if the group has no facts, or the fact
+    /// isn't a problem because the group skipped validation, so the
+    /// caller can use the number range instead.
+    pub fn generate_fact(
+        &mut self,
+        group: &WaveGroup,
+        mastery: Option<&MasteryModel>,
+    ) -> Option<Problem> {
+        let facts = group.facts();
+        if facts.is_empty() {
+            return None;
+        }
+        let key: Vec<Vec<i32>> = facts.iter().map(|fact| fact.operands.clone()).collect();
+        let index = if group.shuffle == Some(true) {
+            self.pick_shuffled_fact(group, &key, mastery)
+        } else {
+            // carries on after the last one asked
+            self.recent_facts
+                .get(&key)
+                .and_then(|recent| recent.front())
+                .and_then(|operands| facts.iter().position(|fact| fact.operands == *operands))
+                .map_or(0, |last| (last + 1) % facts.len())
+        };
+        let fact = &facts[index];
+        // enough to leave out any no_repeat, which is less than the facts
+        let recent = self.recent_facts.entry(key).or_default();
+        recent.push_front(fact.operands.clone());
+        recent.truncate(facts.len());
+
+        let problem = fact_problem(group.operation, &fact.operands)?;
+        Some(match group.missing {
+            Some(missing) => self.ask_missing(&problem, missing).unwrap_or(problem),
+            None => problem,
+        })
+    }
+
+    // leaves out the group's last no_repeat facts, then picks by weight
+    fn pick_shuffled_fact(
+        &mut self,
+        group: &WaveGroup,
+        key: &[Vec<i32>],
+        mastery: Option<&MasteryModel>,
+    ) -> usize {
+        let facts = group.facts();
+        let recent: Vec<&Vec<i32>> = self
+            .recent_facts
+            .get(key)
+            .into_iter()
+            .flatten()
+            .take(group.no_repeat.unwrap_or(0))
+            .collect();
+        let mut candidates: Vec<usize> = (0..facts.len())
+            .filter(|i| !recent.contains(&&facts[*i].operands))
+            .collect();
+        if candidates.is_empty() {
+            candidates = (0..facts.len()).collect();
+        }
+        // a weight of 0, left by a group that skipped validation, is never picked
+        let weighted: Vec<(usize, f32)> = candidates
+            .iter()
+            .map(|i| {
+                let fact = &facts[*i];
+                let problem = fact_problem(group.operation, &fact.operands);
+                let mastery_weight = match (mastery, problem) {
+                    (Some(mastery), Some(problem)) => mastery.weight(&problem.display_text),
+                    _ => 1.0,
+                };
+                (*i, fact.weight as f32 * mastery_weight)
+            })
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect();
+        if weighted.is_empty() {
+            return candidates[0];
+        }
+        let total: f32 = weighted.iter().map(|(_, weight)| weight).sum();
+        let mut pick = self.rng.gen_range(0.0, total);
+        for (candidate, weight) in weighted.iter() {
+            if pick < *weight {
+                return *candidate;
+            }
+            pick -= weight;
+        }
+        weighted[weighted.len() - 1].0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::*;
+
+    const DRAWS: usize = 300;
+
+    fn fact(operands: &[i32], weight: u32) -> Fact {
+        Fact {
+            operands: operands.to_vec(),
+            weight,
+        }
+    }
+
+    fn group(facts: Vec<Fact>, shuffle: Option<bool>, no_repeat: Option<usize>) -> WaveGroup {
+        WaveGroup {
+            operation: Operation::Multiply,
+            speed: 1.0,
+            num_ships: 1,
+            min_number: 1,
+            max_number: 13,
+            facts: Some(facts),
+            shuffle,
+            no_repeat,
+            ..Default::default()
+        }
+    }
+
+    fn draw(generator: &mut ProblemGenerator, group: &WaveGroup) -> Vec<i32> {
+        let problem = generator.generate_fact(group, None).unwrap();
+        problem.operands.iter().map(|n| n.numer).collect()
+    }
+
+    #[test]
+    fn no_repeat_window() {
+        let group = group(
+            vec![fact(&[2, 3], 1), fact(&[4, 5], 1), fact(&[6, 7], 1)],
+            Some(true),
+            Some(2),
+        );
+        assert!(group.validate().is_empty(), "{:?}", group.validate());
+        let mut generator = ProblemGenerator::from_seed(1);
+        let drawn: Vec<Vec<i32>> = (0..DRAWS).map(|_| draw(&mut generator, &group)).collect();
+        for window in drawn.windows(3) {
+            assert_ne!(window[0], window[1]);
+            assert_ne!(window[0], window[2]);
+            assert_ne!(window[1], window[2]);
+        }
+    }
+
+    #[test]
+    fn weighted_sampling() {
+        let group = group(
+            vec![fact(&[2, 3], 1), fact(&[4, 5], 4), fact(&[6, 7], 0)],
+            Some(true),
+            None,
+        );
+        let mut generator = ProblemGenerator::from_seed(2);
+        let mut counts = [0; 2];
+        for _ in 0..DRAWS {
+            match draw(&mut generator, &group).as_slice() {
+                [2, 3] => counts[0] += 1,
+                [4, 5] => counts[1] += 1,
+                other => panic!("drew {:?} with a weight of 0", other),
+            }
+        }
+        assert!(counts[1] > counts[0] * 2, "{:?}", counts);
+    }
+
+    #[test]
+    fn ordered_facts_wrap_around() {
+        let facts = vec![fact(&[2, 3], 1), fact(&[4, 5], 1), fact(&[6, 7], 1)];
+        let group = group(facts.clone(), None, None);
+        let mut generator = ProblemGenerator::from_seed(3);
+        for i in 0..7 {
+            assert_eq!(draw(&mut generator, &group), facts[i % 3].operands);
+        }
+    }
+
+    #[test]
+    fn history_is_per_group() {
+        let first = group(vec![fact(&[2, 3], 1), fact(&[4, 5], 1)], None, None);
+        let second = group(
+            vec![fact(&[8, 9], 1), fact(&[10, 11], 1), fact(&[12, 12], 1)],
+            None,
+            None,
+        );
+        let mut generator = ProblemGenerator::from_seed(4);
+        assert_eq!(draw(&mut generator, &first), vec![2, 3]);
+        assert_eq!(draw(&mut generator, &second), vec![8, 9]);
+        assert_eq!(draw(&mut generator, &second), vec![10, 11]);
+        assert_eq!(draw(&mut generator, &first), vec![4, 5]);
+        assert_eq!(draw(&mut generator, &second), vec![12, 12]);
+        assert_eq!(draw(&mut generator, &first), vec![2, 3]);
+    }
+
+    #[test]
+    fn invalid_facts_are_reported() {
+        let fields = |group: WaveGroup| -> Vec<String> {
+            group.validate_facts().into_iter().map(|error| error.path).collect()
+        };
+        assert_eq!(fields(group(vec![], None, None)), vec!["facts"]);
+        assert_eq!(
+            fields(group(vec![fact(&[2, 3], 1), fact(&[2, 3], 1)], None, None)),
+            vec!["facts[1]"]
+        );
+        assert_eq!(
+            fields(group(vec![fact(&[2, 3], 0)], None, None)),
+            vec!["facts[0].weight"]
+        );
+        assert_eq!(
+            fields(group(vec![fact(&[2, 3, 4], 1)], None, None)),
+            vec!["facts[0]"]
+        );
+        assert_eq!(
+            fields(group(vec![fact(&[2, 3], 1), fact(&[4, 5], 1)], Some(true), Some(2))),
+            vec!["no_repeat"]
+        );
+        assert_eq!(
+            fields(WaveGroup {
+                operation: Operation::Divide,
+                ..group(vec![fact(&[7, 0], 1)], None, None)
+            }),
+            vec!["facts[0]"]
+        );
+        assert_eq!(
+            fields(WaveGroup {
+                facts: None,
+                ..group(vec![], Some(true), None)
+            }),
+            vec!["facts"]
+        );
+    }
+
+    #[test]
+    fn bad_fact_falls_back() {
+        let group = group(vec![fact(&[2, 3, 4], 1)], None, None);
+        let mut generator = ProblemGenerator::from_seed(5);
+        assert!(generator.generate_fact(&group, None).is_none());
+        assert!(generator.generate(&group, &Difficulty::default()).is_valid());
+    }
+}
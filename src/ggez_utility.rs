@@ -1,4 +1,4 @@
-use ggez::graphics::Color;
+use ggez::graphics::{self, Color};
 use ggez::nalgebra as na;
 
 pub const WHITE: Color = Color {
@@ -20,6 +20,13 @@ pub const GRAY: Color = Color {
     a: 1.0,
 };
 
+pub const RED: Color = Color {
+    r: 1.0,
+    g: 0.0,
+    b: 0.0,
+    a: 1.0,
+};
+
 pub const DARK_GRAY: Color = Color {
     r: 0.25,
     g: 0.25,
@@ -27,17 +34,22 @@ pub const DARK_GRAY: Color = Color {
     a: 1.0,
 };
 
-pub trait Scalable {
+// Anything placed on screen in percentages of the window
+pub trait Positioned {
     fn pct_dimensions(&self) -> (f32, f32);
-    fn src_pixel_dimensions(&self) -> (f32, f32);
     fn pct_pos(&self) -> na::Point2<f32>;
     fn dest_pixel_dimensions(&self, screen_dimensions: (f32, f32)) -> (f32, f32) {
         let (w, h) = self.pct_dimensions();
         (w * screen_dimensions.0, h * screen_dimensions.1)
     }
-    fn scale(&self, window_dimensions: (f32, f32)) -> na::Vector2<f32> {
+    // the scale that stretches an image of src_dimensions pixels to fit
+    fn scale_from(
+        &self,
+        src_dimensions: (f32, f32),
+        window_dimensions: (f32, f32),
+    ) -> na::Vector2<f32> {
         let (sw, sh) = self.dest_pixel_dimensions(window_dimensions);
-        let (tw, th) = self.src_pixel_dimensions();
+        let (tw, th) = src_dimensions;
         // only use screen width for scaling
         na::Vector2::new(sw / tw, sh / th)
     }
@@ -47,6 +59,17 @@ pub trait Scalable {
     }
 }
 
+pub trait Scalable: Positioned {
+    fn src_pixel_dimensions(&self) -> (f32, f32);
+    fn scale(&self, window_dimensions: (f32, f32)) -> na::Vector2<f32> {
+        self.scale_from(self.src_pixel_dimensions(), window_dimensions)
+    }
+}
+
+pub fn image_dimensions(img: &graphics::Image) -> (f32, f32) {
+    (img.width() as f32, img.height() as f32)
+}
+
 pub fn to_screen_pos(pos: (f32, f32), screen_dimensions: (f32, f32)) -> na::Point2<f32> {
     na::Point2::new(pos.0 * screen_dimensions.0, pos.1 * screen_dimensions.1)
 }
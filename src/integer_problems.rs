@@ -0,0 +1,217 @@
+use rand::*;
+
+use crate::answer::*;
+use crate::difficulty::*;
+use crate::expression::*;
+use crate::level::*;
+use crate::problem::*;
+use crate::rational::*;
+
+pub const INTEGER_OPERATIONS: [Operation; 4] = [
+    Operation::Power,
+    Operation::SquareRoot,
+    Operation::Modulo,
+    Operation::DivideWithRemainder,
+];
+
+// shown where the quotient and remainder go, filled in when revealed
+pub const REMAINDER_PROMPT: &str = "?r?";
+
+fn integer_sqrt(square: i32) -> Option<i32> {
+    if square < 0 {
+        return None;
+    }
+    let root = (square as f64).sqrt().round() as i32;
+    if root * root == square {
+        Some(root)
+    } else {
+        None
+    }
+}
+
+/// Works the answer out from the operands, None if they don't make a
+/// problem, like the square root of 50
+pub fn integer_answer(operation: Operation, operands: &[Rational]) -> Option<Answer> {
+    if !operands.iter().all(|n| n.is_integer()) {
+        return None;
+    }
+    let numbers: Vec<i32> = operands.iter().map(|n| n.numer).collect();
+    match (operation, numbers.as_slice()) {
+        (Operation::Power, [base, exponent]) if *exponent >= 0 => base
+            .checked_pow(*exponent as u32)
+            .map(|n| Answer::Number(Rational::from(n))),
+        (Operation::SquareRoot, [square]) => {
+            integer_sqrt(*square).map(|root| Answer::Number(Rational::from(root)))
+        }
+        (Operation::Modulo, [dividend, divisor]) if *dividend >= 0 && *divisor > 0 => {
+            Some(Answer::Number(Rational::from(dividend % divisor)))
+        }
+        (Operation::DivideWithRemainder, [dividend, divisor]) if *dividend >= 0 && *divisor > 0 => {
+            Some(Answer::Remainder {
+                quotient: dividend / divisor,
+                remainder: dividend % divisor,
+            })
+        }
+        _ => None,
+    }
+}
+
+impl Problem {
+    /// Power takes a base and exponent, SquareRoot the square, Modulo and
+    /// DivideWithRemainder a dividend and divisor
+    pub fn integer(operation: Operation, numbers: &[i32]) -> Problem {
+        let display_text = match operation {
+            // parentheses keep (-2)^2 from reading as -(2^2)
+            Operation::Power => format!(
+                "{}^{}",
+                number_text(Rational::from(numbers[0]), false, false),
+                numbers[1]
+            ),
+            Operation::SquareRoot => format!("√{}", numbers[0]),
+            Operation::Modulo => format!("{} mod {}", numbers[0], numbers[1]),
+            Operation::DivideWithRemainder => {
+                format!("{}/{}={}", numbers[0], numbers[1], REMAINDER_PROMPT)
+            }
+            _ => panic!("{:?} is not an integer problem", operation),
+        };
+        let operands: Vec<Rational> = numbers.iter().map(|n| Rational::from(*n)).collect();
+        Problem {
+            answer: integer_answer(operation, &operands)
+                .unwrap_or_else(|| Answer::Number(Rational::zero())),
+            operands,
+            operation,
+            display_text,
+            expression: None,
+            missing: None,
+        }
+    }
+}
+
+impl<R: Rng> ProblemGenerator<R> {
+    pub fn generate_integer(&mut self, group: &WaveGroup, difficulty: &Difficulty) -> Problem {
+        let (min_number, max_number) =
+            scale_range(group.min_number, group.max_number, difficulty);
+        let max_answer = group.max_answer();
+        let numbers = match group.operation {
+            Operation::Power => {
+                let (min_exponent, max_exponent) = group.exponent_range();
+                let exponent = self.rng.gen_range(min_exponent, max_exponent);
+                // smaller bases until it fits, 0 or 1 always does
+                let mut base = self.rng.gen_range(min_number, max_number);
+                while base
+                    .checked_pow(exponent)
+                    .is_none_or(|n| n.unsigned_abs() > max_answer as u32)
+                {
+                    base -= base.signum();
+                }
+                vec![base, exponent as i32]
+            }
+            Operation::SquareRoot => {
+                let mut root = self.rng.gen_range(min_number, max_number).abs().min(max_answer);
+                while root.checked_mul(root).is_none() {
+                    root /= 2;
+                }
+                vec![root * root]
+            }
+            _ => {
+                // built backwards like Divide, remainders are always less
+                // than the divisor, and no more than max_answer
+                let (min_divisor, max_divisor) = group.divisor_range();
+                let (min_divisor, max_divisor) = scale_range(min_divisor, max_divisor, difficulty);
+                let (min_quotient, max_quotient) = group.quotient_range();
+                let (min_quotient, max_quotient) =
+                    scale_range(min_quotient, max_quotient, difficulty);
+                let divisor = gen_nonzero(&mut self.rng, min_divisor, max_divisor).abs();
+                let quotient = self.rng.gen_range(min_quotient, max_quotient).abs().min(max_answer);
+                let remainder = self
+                    .rng
+                    .gen_range(0, divisor.min(max_answer.saturating_add(1)));
+                vec![divisor * quotient + remainder, divisor]
+            }
+        };
+        let problem = Problem::integer(group.operation, &numbers);
+        debug_assert!(problem.is_valid(), "invalid problem {:?}", problem);
+        problem
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBLEMS: usize = 200;
+
+    fn within(answer: Answer, max_answer: i32) -> bool {
+        match answer {
+            Answer::Number(n) => n
+                .checked_abs()
+                .is_some_and(|n| n <= Rational::from(max_answer)),
+            Answer::Remainder {
+                quotient,
+                remainder,
+            } => quotient.abs() <= max_answer && remainder.abs() <= max_answer,
+            Answer::YesNo(_) => false,
+        }
+    }
+
+    #[test]
+    fn answers_stay_within_max_answer() {
+        let mut generator = ProblemGenerator::from_seed(5);
+        let hardest = Difficulty::new().pop().unwrap();
+        for operation in INTEGER_OPERATIONS.iter() {
+            for (min_number, max_number) in [(-2, -1), (-12, 12), (1, 100), (50, 1000)].iter() {
+                for max_answer in [1, 20, 999].iter() {
+                    let group = WaveGroup {
+                        operation: *operation,
+                        speed: 1.0,
+                        num_ships: 1,
+                        min_number: *min_number,
+                        max_number: *max_number,
+                        min_exponent: Some(1),
+                        max_exponent: Some(MAX_EXPONENT),
+                        max_answer: Some(*max_answer),
+                        ..Default::default()
+                    };
+                    assert!(group.validate().is_empty(), "{:?}", group.validate());
+                    for _ in 0..PROBLEMS {
+                        let problem = generator.generate(&group, &hardest);
+                        assert!(problem.is_valid(), "{:?}", problem);
+                        assert!(within(problem.answer, *max_answer), "{:?}", problem);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn exponents_are_capped() {
+        let group = WaveGroup {
+            operation: Operation::Power,
+            speed: 1.0,
+            num_ships: 1,
+            min_number: 1,
+            max_number: 3,
+            max_exponent: Some(MAX_EXPONENT + 1),
+            ..Default::default()
+        };
+        assert_eq!(group.validate().len(), 1);
+    }
+
+    #[test]
+    fn remainders_parse_and_check() {
+        let problem = Problem::integer(Operation::DivideWithRemainder, &[23, 3]);
+        assert_eq!(
+            "7r2".parse::<Answer>(),
+            Ok(Answer::Remainder {
+                quotient: 7,
+                remainder: 2
+            })
+        );
+        assert_eq!(problem.answer_text(), "7r2");
+        assert!(problem.is_answer("7r2"));
+        assert!(problem.is_answer(" 7 r 2 "));
+        assert!(!problem.is_answer("7r3"));
+        assert!(!problem.is_answer("7"));
+        assert!(!problem.is_answer("r2"));
+    }
+}
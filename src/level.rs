@@ -1,91 +1,644 @@
-use crate::assets::*;
-use crate::message::*;
-use ggez::Context;
+use crate::data_file::*;
+use crate::difficulty::*;
+use crate::expression::*;
+use crate::facts::*;
 
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
-use std::fs::File;
-use std::io::{Read, Write};
+use std::fmt;
+use std::path::Path;
 use std::str;
 
-#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq)]
+pub const LEVEL_FILE: &str = "resources/levels.json";
+
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Default)]
 pub enum Operation {
+    #[default]
     Add,
     Subtract,
     Multiply,
     Divide,
+    // three or more numbers with precedence and parentheses, like (3+4)X2
+    Expression,
+    // answered as a reduced fraction like 3/4
+    Fraction,
+    // answered as a decimal like 1.25
+    Decimal,
+    // which of two fractions is bigger, answered with that fraction
+    Compare,
+    // round a number to the nearest 10, 100 or 1000
+    Round,
+    // is a number prime, answered y or n
+    Prime,
+    // one digit of a number, like the tens digit of 347
+    PlaceValue,
+    // a number from min_number..max_number raised to an exponent from
+    // min_exponent..max_exponent, like 2^5
+    Power,
+    // the root comes from min_number..max_number, so it is always a
+    // perfect square like √49
+    SquareRoot,
+    // built from the divisor and quotient ranges like Divide, plus a
+    // remainder, answered with the remainder
+    Modulo,
+    // same numbers as Modulo, answered like 7r2
+    DivideWithRemainder,
+}
+
+impl Operation {
+    // the four operations a missing number or sign can be asked for
+    pub fn is_basic(&self) -> bool {
+        matches!(
+            self,
+            Operation::Add | Operation::Subtract | Operation::Multiply | Operation::Divide
+        )
+    }
+
+    /// Number sense problems are about one or two numbers rather than
+    /// working something out from them
+    pub fn is_number_sense(&self) -> bool {
+        matches!(
+            self,
+            Operation::Compare | Operation::Round | Operation::Prime | Operation::PlaceValue
+        )
+    }
+}
+
+/// Which difficulty tiers a level starts unlocked on, true or false for
+/// every tier or a list of tier names like ["Rookie"]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Unlocked {
+    Everywhere(bool),
+    Tiers(Vec<String>),
+    // older level files, one flag per built in tier in order, tiers
+    // that aren't built in use the last flag
+    Flags(Vec<bool>),
+}
+
+impl Unlocked {
+    pub fn on(&self, tier: &str) -> bool {
+        match self {
+            Unlocked::Everywhere(unlocked) => *unlocked,
+            Unlocked::Tiers(tiers) => tiers.iter().any(|name| name == tier),
+            Unlocked::Flags(flags) => Difficulty::new()
+                .iter()
+                .position(|difficulty| difficulty.name == tier)
+                .and_then(|i| flags.get(i))
+                .or_else(|| flags.last())
+                .cloned()
+                .unwrap_or(false),
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize)]
+// Levels are read only content, player progress lives in Progress
+#[derive(Deserialize, Serialize, Clone)]
 pub struct Level {
+    // stable key for saved progress, falls back to the title when missing
+    #[serde(default)]
+    pub id: String,
     pub waves: Vec<Wave>,
     pub background_file: String,
     pub title: String,
-    pub unlocked: [bool; 4],
+    pub unlocked: Unlocked,
+    #[serde(default)]
+    pub penalty: PenaltyMode,
+    // show an alien's answer after this many wrong answers on it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reveal_after: Option<u32>,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Clone)]
 pub struct Wave {
     pub groups: Vec<WaveGroup>,
 }
 
-#[derive(Deserialize, Serialize)]
+// what a wrong answer costs, None just plays the fail sound
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq, Default)]
+pub enum PenaltyMode {
+    #[default]
+    None,
+    // the turret can't fire for LOCKOUT_MS
+    Lockout,
+    // the targeted alien moves faster
+    SpeedUp,
+    // SCORE_PENALTY points are taken off
+    ScorePenalty,
+}
+
+pub const LOCKOUT_MS: f32 = 1500.0;
+pub const SPEED_UP_PENALTY: f32 = 1.25;
+pub const SCORE_PENALTY: u32 = 25;
+
+// which operand to ask for instead of the result, like ?X6=42
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq)]
+pub enum Missing {
+    First,
+    Second,
+    Either,
+}
+
+// the sign an operand gets, its size still comes from min_number..max_number
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq)]
+pub enum Sign {
+    Positive,
+    Negative,
+    // negative half the time
+    Either,
+}
+
+// when answers may go below zero, like 3-5 or a boss chain taking
+// away more than it has, overriding the difficulty's negative_answers
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, PartialEq)]
+pub enum NegativeAnswers {
+    Never,
+    Always,
+}
+
+// Power exponents when a WaveGroup doesn't say, squares and cubes
+pub const DEFAULT_EXPONENTS: (u32, u32) = (2, 4);
+// 2^31 already doesn't fit in an i32
+pub const MAX_EXPONENT: u32 = 31;
+// keeps answers to something a kid can type, unless a WaveGroup says otherwise
+pub const DEFAULT_MAX_ANSWER: i32 = 999;
+// the biggest number a range can reach, even scaled by a difficulty, so
+// that 46340X46340 still fits in an i32
+pub const MAX_OPERAND: i32 = 46340;
+
+// All number ranges are min inclusive, max exclusive
+#[derive(Deserialize, Serialize, Default, Clone)]
 pub struct WaveGroup {
     pub operation: Operation,
     pub speed: f32,
     pub num_ships: usize,
+    // not needed when the group lists its facts
+    #[serde(default)]
     pub max_number: i32,
+    #[serde(default)]
     pub min_number: i32,
+    // Divide problems are built as divisor x quotient, these default
+    // to min_number/max_number when not given. Zero is never a divisor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_divisor: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_divisor: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_quotient: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_quotient: Option<i32>,
+    // Makes every ship in the group a boss that takes this many right
+    // answers, each problem after the first starts from the last answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boss_steps: Option<usize>,
+    // Expression groups build trees this deep, DEFAULT_EXPRESSION_DEPTH
+    // when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expression_depth: Option<usize>,
+    // what Expression, Fraction and Decimal problems are made of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operations: Option<Vec<Operation>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing: Option<Missing>,
+    // Add, Subtract, Multiply and Divide operands keep the sign they
+    // were drawn with when these aren't given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_sign: Option<Sign>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub second_sign: Option<Sign>,
+    // up to the difficulty when not given. Signs given above are always
+    // used, even if that makes the answer negative.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub negative_answers: Option<NegativeAnswers>,
+    // Power exponents, DEFAULT_EXPONENTS when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_exponent: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_exponent: Option<u32>,
+    // Power, SquareRoot, DivideWithRemainder and Expression answers stay
+    // within plus or minus this, DEFAULT_MAX_ANSWER when not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_answer: Option<i32>,
+    // the exact problems to ask instead of random ones, like [[8, 7], [6, 9]]
+    // for Multiply. The number ranges above aren't used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub facts: Option<Vec<Fact>>,
+    // facts are asked in the order listed unless shuffled, then they are
+    // picked at random by weight
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shuffle: Option<bool>,
+    // a shuffled fact won't come back until this many others have been asked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub no_repeat: Option<usize>,
 }
 
-pub const DIFFICULTY_NAMES: [&str; 4] = ["Rookie", "Cadet", "Veteran", "Space Marine"];
-pub const SPEED_DIFFICULTY: [f32; 4] = [1.0, 1.1, 1.25, 1.5];
-pub const MAX_NUMBER_DIFFICULTY: [f32; 4] = [1.0, 1.25, 2.0, 3.0];
-pub const MIN_NUMBER_DIFFICULTY: [f32; 4] = [1.0, 1.25, 2.0, 3.0];
-pub const NUM_SHIPS_DIFFICULTY: [f32; 4] = [1.0, 1.25, 2.0, 3.0];
+impl WaveGroup {
+    pub fn divisor_range(&self) -> (i32, i32) {
+        (
+            self.min_divisor.unwrap_or(self.min_number),
+            self.max_divisor.unwrap_or(self.max_number),
+        )
+    }
 
-impl Level {
-    pub fn push_title(&self, messages: &mut VecDeque<Message>, assets: &Assets, ctx: &mut Context) {
-        messages.push_back(Message::new(self.title.clone(), 2000.0, assets, ctx));
+    pub fn quotient_range(&self) -> (i32, i32) {
+        (
+            self.min_quotient.unwrap_or(self.min_number),
+            self.max_quotient.unwrap_or(self.max_number),
+        )
     }
 
-    pub fn load_from_file() -> Vec<Level> {
-        //if any of this fails, call new instead
-        fn load_helper() -> Result<Vec<Level>, String> {
-            let mut file = File::open("resources/levels.json")
-                .map_err(|e| format!("file not found\n {}", e))?;
-            let mut buffer = Vec::new();
-            file.read_to_end(&mut buffer)
-                .map_err(|e| format!("file could not be read\n{}", e))?;
-            let level: Vec<Level> = serde_json::from_slice(&buffer[..])
-                .map_err(|e| format!("file not valid\n{}", e))?;
-            Ok(level)
+    pub fn exponent_range(&self) -> (u32, u32) {
+        (
+            self.min_exponent.unwrap_or(DEFAULT_EXPONENTS.0),
+            self.max_exponent.unwrap_or(DEFAULT_EXPONENTS.1),
+        )
+    }
+
+    pub fn max_answer(&self) -> i32 {
+        self.max_answer.unwrap_or(DEFAULT_MAX_ANSWER)
+    }
+
+    pub fn expression_depth(&self) -> usize {
+        self.expression_depth.unwrap_or(DEFAULT_EXPRESSION_DEPTH)
+    }
+
+    /// Every generator keeps answers, and each step on the way to them,
+    /// at zero or above until the level or difficulty allows negatives
+    pub fn allows_negative_answers(&self, difficulty: &Difficulty) -> bool {
+        match self.negative_answers {
+            Some(NegativeAnswers::Never) => false,
+            Some(NegativeAnswers::Always) => true,
+            None => difficulty.negative_answers,
+        }
+    }
+
+    // a - b would go below zero when that isn't allowed yet
+    pub fn goes_negative<T: PartialOrd>(
+        &self,
+        difficulty: &Difficulty,
+        operation: Operation,
+        a: T,
+        b: T,
+    ) -> bool {
+        operation == Operation::Subtract && b > a && !self.allows_negative_answers(difficulty)
+    }
+
+    pub fn operations(&self) -> Vec<Operation> {
+        match (&self.operations, self.operation) {
+            (Some(operations), _) => operations.clone(),
+            (None, Operation::Expression) => DEFAULT_EXPRESSION_OPERATIONS.to_vec(),
+            (None, Operation::Fraction) | (None, Operation::Decimal) => {
+                vec![Operation::Add, Operation::Subtract]
+            }
+            (None, operation) => vec![operation],
+        }
+    }
+
+    /// Returns a list of reasons this group can't produce any problems
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let mut errors = self.validate_facts();
+        // the rest only matters for random problems
+        let ranged = self.facts.is_none();
+        if ranged && self.min_number >= self.max_number {
+            errors.push(ValidationError::new(
+                "min_number",
+                format!(
+                    "min_number ({}) must be less than max_number ({})",
+                    self.min_number, self.max_number
+                ),
+            ));
         }
+        if ranged {
+            // answers have to fit in an i32
+            let bounds = [
+                ("min_number", Some(self.min_number)),
+                ("max_number", Some(self.max_number)),
+                ("min_divisor", self.min_divisor),
+                ("max_divisor", self.max_divisor),
+                ("min_quotient", self.min_quotient),
+                ("max_quotient", self.max_quotient),
+            ];
+            for (path, bound) in bounds.iter() {
+                if let Some(n) = bound.filter(|n| !(-MAX_OPERAND..=MAX_OPERAND).contains(n)) {
+                    errors.push(ValidationError::new(
+                        *path,
+                        format!(
+                            "{} ({}) must be from -{} to {}",
+                            path, n, MAX_OPERAND, MAX_OPERAND
+                        ),
+                    ));
+                }
+            }
+        }
+        if self.speed <= 0.0 || self.speed.is_nan() {
+            errors.push(ValidationError::new(
+                "speed",
+                format!("speed ({}) must be positive", self.speed),
+            ));
+        }
+        if let Some(steps) = self.boss_steps {
+            if steps < 2 {
+                errors.push(ValidationError::new(
+                    "boss_steps",
+                    format!("boss_steps ({}) must be at least 2", steps),
+                ));
+            }
+        }
+        if let Some(max_answer) = self.max_answer {
+            if max_answer < 1 {
+                errors.push(ValidationError::new(
+                    "max_answer",
+                    format!("max_answer ({}) must be at least 1", max_answer),
+                ));
+            }
+        }
+        if ranged && self.operation == Operation::Power {
+            let (min_exponent, max_exponent) = self.exponent_range();
+            if min_exponent >= max_exponent {
+                errors.push(ValidationError::new(
+                    "min_exponent",
+                    format!("exponent range {}..{} is empty", min_exponent, max_exponent),
+                ));
+            }
+            if max_exponent > MAX_EXPONENT {
+                errors.push(ValidationError::new(
+                    "max_exponent",
+                    format!(
+                        "max_exponent ({}) can be at most {}",
+                        max_exponent, MAX_EXPONENT
+                    ),
+                ));
+            }
+        }
+        if self.operation == Operation::Expression {
+            let depth = self.expression_depth();
+            if !(1..=MAX_EXPRESSION_DEPTH).contains(&depth) {
+                errors.push(ValidationError::new(
+                    "expression_depth",
+                    format!(
+                        "expression_depth ({}) must be from 1 to {}",
+                        depth, MAX_EXPRESSION_DEPTH
+                    ),
+                ));
+            }
+        }
+        let fractions = [Operation::Fraction, Operation::Compare].contains(&self.operation);
+        if ranged && fractions && self.max_number <= 2 {
+            errors.push(ValidationError::new(
+                "max_number",
+                format!(
+                    "max_number ({}) must be above 2, it limits the denominators",
+                    self.max_number
+                ),
+            ));
+        }
+        if self.missing.is_some() && !self.operation.is_basic() {
+            errors.push(ValidationError::new(
+                "missing",
+                format!("{:?} problems can't have a missing number", self.operation),
+            ));
+        }
+        if !self.operation.is_basic() {
+            let signs = [("first_sign", self.first_sign), ("second_sign", self.second_sign)];
+            for (path, sign) in signs.iter() {
+                if sign.is_some() {
+                    errors.push(ValidationError::new(
+                        *path,
+                        format!("{:?} problems can't set operand signs", self.operation),
+                    ));
+                }
+            }
+        }
+        let mixed = [Operation::Expression, Operation::Fraction, Operation::Decimal];
+        if mixed.contains(&self.operation) {
+            let operations = self.operations();
+            // a fraction bar and a divide sign look the same
+            let allowed: &[Operation] = if self.operation == Operation::Expression {
+                &[Operation::Add, Operation::Subtract, Operation::Multiply, Operation::Divide]
+            } else {
+                &[Operation::Add, Operation::Subtract, Operation::Multiply]
+            };
+            if operations.is_empty() {
+                errors.push(ValidationError::new(
+                    "operations",
+                    "operations is empty".to_string(),
+                ));
+            } else if let Some(bad) = operations.iter().find(|op| !allowed.contains(op)) {
+                errors.push(ValidationError::new(
+                    "operations",
+                    format!("{:?} problems can't contain {:?}", self.operation, bad),
+                ));
+            }
+        }
+        let divides = [Operation::Divide, Operation::Modulo, Operation::DivideWithRemainder];
+        if ranged && divides.contains(&self.operation) {
+            // the defaults come from min_number/max_number, which were checked above
+            let (min_divisor, max_divisor) = self.divisor_range();
+            let (min_quotient, max_quotient) = self.quotient_range();
+            let explicit_divisor = self.min_divisor.is_some() || self.max_divisor.is_some();
+            let explicit_quotient = self.min_quotient.is_some() || self.max_quotient.is_some();
+            let numbers_ok = self.min_number < self.max_number;
+            if min_divisor >= max_divisor && (explicit_divisor || numbers_ok) {
+                errors.push(ValidationError::new(
+                    "min_divisor",
+                    format!("divisor range {}..{} is empty", min_divisor, max_divisor),
+                ));
+            } else if min_divisor == 0 && max_divisor == 1 {
+                errors.push(ValidationError::new(
+                    "min_divisor",
+                    "divisor range only contains 0".to_string(),
+                ));
+            }
+            if min_quotient >= max_quotient && (explicit_quotient || numbers_ok) {
+                errors.push(ValidationError::new(
+                    "min_quotient",
+                    format!("quotient range {}..{} is empty", min_quotient, max_quotient),
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// A problem found in a level file. `path` points at the offending
+/// field, like `levels[2].waves[0].groups[1].max_number`, or at the
+/// line and column for syntax errors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub path: String,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new<S: Into<String>>(path: S, message: String) -> ValidationError {
+        ValidationError {
+            path: path.into(),
+            message,
+        }
+    }
+
+    /// A syntax error, serde appends the position to its message and we
+    /// report it as the path
+    pub fn from_json(e: &serde_json::Error) -> ValidationError {
+        let message = e.to_string();
+        let location = format!(" at line {} column {}", e.line(), e.column());
+        ValidationError::new(
+            format!("line {} column {}", e.line(), e.column()),
+            message.trim_end_matches(&location[..]).to_string(),
+        )
+    }
+
+    pub fn prefixed(self, prefix: &str) -> ValidationError {
+        ValidationError {
+            path: prefix.to_string() + "." + &self.path,
+            message: self.message,
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Lowercases and joins the words with dashes: "The Final Assault!" -> "the-final-assault"
+pub fn slugify(s: &str) -> String {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+impl Level {
+    pub fn id(&self) -> String {
+        if self.id.is_empty() {
+            slugify(&self.title)
+        } else {
+            self.id.clone()
+        }
+    }
 
-        let result = load_helper();
-        match result {
-            Ok(levels) => levels,
-            Err(msg) => {
-                // If we get an error, load the default and save a new levels.json file
-                println!("Error loading level file.\nUsing default\n{}", msg);
-                let levels = Level::new();
-                Level::save_levels(&levels);                    
-                levels
+    pub fn starts_unlocked(&self, tier: &str) -> bool {
+        self.unlocked.on(tier)
+    }
+
+    /// Checks a level's content, background files are looked up in resource_dir
+    pub fn validate(&self, resource_dir: &Path) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if self.title.trim().is_empty() {
+            errors.push(ValidationError::new("title", "title is empty".to_string()));
+        }
+        let background = resource_dir.join(self.background_file.trim_start_matches('/'));
+        if !background.is_file() {
+            errors.push(ValidationError::new(
+                "background_file",
+                format!("{} not found in {}", self.background_file, resource_dir.display()),
+            ));
+        }
+        if self.unlocked == Unlocked::Tiers(Vec::new()) {
+            errors.push(ValidationError::new(
+                "unlocked",
+                "list at least one tier, or use false".to_string(),
+            ));
+        }
+        if self.reveal_after == Some(0) {
+            errors.push(ValidationError::new(
+                "reveal_after",
+                "must be at least 1, leave it out to never reveal answers".to_string(),
+            ));
+        }
+        if self.waves.is_empty() {
+            errors.push(ValidationError::new("waves", "level has no waves".to_string()));
+        }
+        for (i, wave) in self.waves.iter().enumerate() {
+            let wave_path = format!("waves[{}]", i);
+            if wave.groups.is_empty() {
+                errors.push(ValidationError::new(
+                    wave_path.clone() + ".groups",
+                    "wave has no groups".to_string(),
+                ));
+            }
+            for (j, group) in wave.groups.iter().enumerate() {
+                let group_path = format!("{}.groups[{}]", wave_path, j);
+                errors.extend(group.validate().into_iter().map(|e| e.prefixed(&group_path)));
             }
         }
+        errors
     }
 
-    pub fn save_levels(levels: &Vec<Level>) {
-        fn save_helper(levels: &Vec<Level>) -> Result<(),String> {            
-            let serialized = serde_json::to_string_pretty(&levels).map_err(|_| "couldn't serialize levels")?; 
-            let mut file = File::create("resources/levels.json").map_err(|_| "couldn't create save file for levels")?;
-            file.write_all(serialized.as_bytes()).map_err(|_| "couldn't wire to save file")?;
-            Ok(())
+    pub fn validate_levels(levels: &[Level], resource_dir: &Path) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if levels.is_empty() {
+            errors.push(ValidationError::new("levels", "there are no levels".to_string()));
+        }
+        // otherwise a tier could have nothing to play
+        if let Some(first) = levels.first() {
+            let everywhere = match &first.unlocked {
+                Unlocked::Everywhere(unlocked) => *unlocked,
+                Unlocked::Tiers(_) => false,
+                Unlocked::Flags(flags) => flags.iter().all(|unlocked| *unlocked),
+            };
+            if !everywhere {
+                errors.push(ValidationError::new(
+                    "levels[0].unlocked",
+                    "the first level has to start unlocked on every tier, use true".to_string(),
+                ));
+            }
+        }
+        for (i, level) in levels.iter().enumerate() {
+            let level_path = format!("levels[{}]", i);
+            if let Some(first) = levels[..i].iter().position(|other| other.id() == level.id()) {
+                errors.push(ValidationError::new(
+                    level_path.clone() + ".id",
+                    format!("id \"{}\" is already used by levels[{}]", level.id(), first),
+                ));
+            }
+            errors.extend(
+                level
+                    .validate(resource_dir)
+                    .into_iter()
+                    .map(|e| e.prefixed(&level_path)),
+            );
         }
-        match save_helper(&levels) {
-            Err(msg) => println!("{}",msg),
-            Ok(_) => ()
+        errors
+    }
+
+    /// Tier names in unlocked that aren't in the difficulty file, which
+    /// would leave a level locked on a tier that was misspelled
+    pub fn validate_tiers(levels: &[Level], difficulties: &[Difficulty]) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        for (i, level) in levels.iter().enumerate() {
+            if let Unlocked::Tiers(tiers) = &level.unlocked {
+                for (j, tier) in tiers.iter().enumerate() {
+                    if !difficulties.iter().any(|difficulty| difficulty.name == *tier) {
+                        errors.push(ValidationError::new(
+                            format!("levels[{}].unlocked[{}]", i, j),
+                            format!("there is no difficulty named \"{}\"", tier),
+                        ));
+                    }
+                }
+            }
         }
+        errors
+    }
+
+    /// Parses and validates a level file without needing a ggez context,
+    /// background files are looked up next to the level file.
+    pub fn validate_file(path: &Path) -> Result<Vec<Level>, Vec<ValidationError>> {
+        let resource_dir = path.parent().unwrap_or_else(|| Path::new("."));
+        read_validated(path, |levels: &Vec<Level>| Level::validate_levels(levels, resource_dir))
+    }
+
+    // what LEVEL_FILE is checked with before loading or saving over it
+    fn validate_level_file(levels: &[Level]) -> Vec<ValidationError> {
+        let resource_dir = Path::new(LEVEL_FILE).parent().unwrap_or_else(|| Path::new("."));
+        Level::validate_levels(levels, resource_dir)
+    }
+
+    pub fn load_from_file() -> Vec<Level> {
+        let validate = |levels: &Vec<Level>| Level::validate_level_file(levels);
+        load_or_create(LEVEL_FILE, "level", validate, Level::new)
     }
 
     // consider validating max_number to be sure it makes sense
@@ -93,7 +646,10 @@ impl Level {
         vec![
             //Level 1
             Level {
-                unlocked: [true, true, true, true],
+                id: "addition-attack".to_string(),
+                unlocked: Unlocked::Everywhere(true),
+                penalty: PenaltyMode::None,
+                reveal_after: None,
                 title: "Addition Attack!".to_string(),
                 background_file: "/spacebg1.jpg".to_string(),
                 waves: vec![
@@ -104,6 +660,7 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Add,
                             num_ships: 5,
+                            ..Default::default()
                         }],
                     },
                     Wave {
@@ -113,6 +670,7 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Add,
                             num_ships: 8,
+                            ..Default::default()
                         }],
                     },
                     Wave {
@@ -122,13 +680,17 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Add,
                             num_ships: 10,
+                            ..Default::default()
                         }],
                     },
                 ],
             },
             //Level 2
             Level {
-                unlocked: [false, false, false, false],
+                id: "subtraction-subterfuge".to_string(),
+                unlocked: Unlocked::Everywhere(false),
+                penalty: PenaltyMode::None,
+                reveal_after: None,
                 title: "Subtraction Subterfuge!".to_string(),
                 background_file: "/spacebg2.jpg".to_string(),
                 waves: vec![
@@ -139,6 +701,7 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Subtract,
                             num_ships: 5,
+                            ..Default::default()
                         }],
                     },
                     Wave {
@@ -148,6 +711,7 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Subtract,
                             num_ships: 8,
+                            ..Default::default()
                         }],
                     },
                     Wave {
@@ -157,13 +721,17 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Subtract,
                             num_ships: 10,
+                            ..Default::default()
                         }],
                     },
                 ],
             },
             //Level 3
             Level {
-                unlocked: [false, false, false, false],
+                id: "multiplication-mayhem".to_string(),
+                unlocked: Unlocked::Everywhere(false),
+                penalty: PenaltyMode::None,
+                reveal_after: None,
                 title: "Multiplication Mayhem!".to_string(),
                 background_file: "/spacebg3.jpg".to_string(),
                 waves: vec![
@@ -174,6 +742,7 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Multiply,
                             num_ships: 5,
+                            ..Default::default()
                         }],
                     },
                     Wave {
@@ -183,6 +752,7 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Multiply,
                             num_ships: 8,
+                            ..Default::default()
                         }],
                     },
                     Wave {
@@ -192,13 +762,17 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Multiply,
                             num_ships: 10,
+                            ..Default::default()
                         }],
                     },
                 ],
             },
             //Level 4
             Level {
-                unlocked: [false, false, false, false],
+                id: "division-disaster".to_string(),
+                unlocked: Unlocked::Everywhere(false),
+                penalty: PenaltyMode::None,
+                reveal_after: None,
                 title: "Division Disaster!".to_string(),
                 background_file: "/spacebg4.jpg".to_string(),
                 waves: vec![
@@ -209,6 +783,7 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Divide,
                             num_ships: 5,
+                            ..Default::default()
                         }],
                     },
                     Wave {
@@ -218,6 +793,7 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Divide,
                             num_ships: 8,
+                            ..Default::default()
                         }],
                     },
                     Wave {
@@ -227,13 +803,17 @@ impl Level {
                             min_number: 0,
                             operation: Operation::Divide,
                             num_ships: 10,
+                            ..Default::default()
                         }],
                     },
                 ],
             },
             //Level 5
             Level {
-                unlocked: [false, false, false, false],
+                id: "final-assault".to_string(),
+                unlocked: Unlocked::Everywhere(false),
+                penalty: PenaltyMode::None,
+                reveal_after: None,
                 title: "The Final Assault!".to_string(),
                 background_file: "/spacebg5.jpg".to_string(),
                 waves: vec![
@@ -245,6 +825,7 @@ impl Level {
                                 min_number: 0,
                                 operation: Operation::Add,
                                 num_ships: 5,
+                                ..Default::default()
                             },
                             WaveGroup {
                                 speed: 2.5,
@@ -252,6 +833,7 @@ impl Level {
                                 min_number: 0,
                                 operation: Operation::Subtract,
                                 num_ships: 5,
+                                ..Default::default()
                             },
                         ],
                     },
@@ -263,6 +845,7 @@ impl Level {
                                 min_number: 0,
                                 operation: Operation::Add,
                                 num_ships: 3,
+                                ..Default::default()
                             },
                             WaveGroup {
                                 speed: 2.5,
@@ -270,6 +853,7 @@ impl Level {
                                 min_number: 0,
                                 operation: Operation::Subtract,
                                 num_ships: 3,
+                                ..Default::default()
                             },
                             WaveGroup {
                                 speed: 2.5,
@@ -277,6 +861,7 @@ impl Level {
                                 min_number: 0,
                                 operation: Operation::Multiply,
                                 num_ships: 3,
+                                ..Default::default()
                             },
                         ],
                     },
@@ -288,6 +873,7 @@ impl Level {
                                 min_number: 0,
                                 operation: Operation::Add,
                                 num_ships: 3,
+                                ..Default::default()
                             },
                             WaveGroup {
                                 speed: 3.5,
@@ -295,6 +881,7 @@ impl Level {
                                 min_number: 0,
                                 operation: Operation::Subtract,
                                 num_ships: 3,
+                                ..Default::default()
                             },
                             WaveGroup {
                                 speed: 2.5,
@@ -302,6 +889,7 @@ impl Level {
                                 min_number: 0,
                                 operation: Operation::Multiply,
                                 num_ships: 3,
+                                ..Default::default()
                             },
                             WaveGroup {
                                 speed: 1.5,
@@ -309,6 +897,91 @@ impl Level {
                                 min_number: 0,
                                 operation: Operation::Divide,
                                 num_ships: 3,
+                                ..Default::default()
+                            },
+                        ],
+                    },
+                    // the mothership
+                    Wave {
+                        groups: vec![
+                            WaveGroup {
+                                speed: 1.0,
+                                max_number: 5,
+                                min_number: 0,
+                                operation: Operation::Multiply,
+                                num_ships: 1,
+                                boss_steps: Some(3),
+                                ..Default::default()
+                            },
+                            WaveGroup {
+                                speed: 2.5,
+                                max_number: 5,
+                                min_number: 0,
+                                operation: Operation::Add,
+                                num_ships: 3,
+                                ..Default::default()
+                            },
+                        ],
+                    },
+                ],
+            },
+            //Level 6
+            Level {
+                id: "below-zero".to_string(),
+                unlocked: Unlocked::Everywhere(false),
+                penalty: PenaltyMode::None,
+                reveal_after: None,
+                title: "Below Zero!".to_string(),
+                background_file: "/spacebg2.jpg".to_string(),
+                waves: vec![
+                    Wave {
+                        groups: vec![WaveGroup {
+                            speed: 2.5,
+                            max_number: 6,
+                            min_number: 0,
+                            operation: Operation::Add,
+                            num_ships: 5,
+                            second_sign: Some(Sign::Negative),
+                            negative_answers: Some(NegativeAnswers::Always),
+                            ..Default::default()
+                        }],
+                    },
+                    Wave {
+                        groups: vec![WaveGroup {
+                            speed: 3.0,
+                            max_number: 6,
+                            min_number: 0,
+                            operation: Operation::Subtract,
+                            num_ships: 8,
+                            first_sign: Some(Sign::Either),
+                            second_sign: Some(Sign::Negative),
+                            negative_answers: Some(NegativeAnswers::Always),
+                            ..Default::default()
+                        }],
+                    },
+                    Wave {
+                        groups: vec![
+                            WaveGroup {
+                                speed: 3.5,
+                                max_number: 6,
+                                min_number: 0,
+                                operation: Operation::Add,
+                                num_ships: 4,
+                                first_sign: Some(Sign::Either),
+                                second_sign: Some(Sign::Either),
+                                negative_answers: Some(NegativeAnswers::Always),
+                                ..Default::default()
+                            },
+                            WaveGroup {
+                                speed: 2.5,
+                                max_number: 6,
+                                min_number: 0,
+                                operation: Operation::Multiply,
+                                num_ships: 4,
+                                first_sign: Some(Sign::Either),
+                                second_sign: Some(Sign::Either),
+                                negative_answers: Some(NegativeAnswers::Always),
+                                ..Default::default()
                             },
                         ],
                     },
@@ -317,3 +990,55 @@ impl Level {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unlocked(json: &str) -> Unlocked {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn unlocked_shapes_parse() {
+        assert_eq!(unlocked("true"), Unlocked::Everywhere(true));
+        assert_eq!(unlocked("false"), Unlocked::Everywhere(false));
+        assert_eq!(
+            unlocked(r#"["Rookie", "Kindergarten"]"#),
+            Unlocked::Tiers(vec!["Rookie".to_string(), "Kindergarten".to_string()])
+        );
+        assert_eq!(
+            unlocked("[true, false, false, false]"),
+            Unlocked::Flags(vec![true, false, false, false])
+        );
+        assert!(serde_json::from_str::<Unlocked>(r#"[true, "Rookie"]"#).is_err());
+        assert!(serde_json::from_str::<Unlocked>("1").is_err());
+    }
+
+    #[test]
+    fn unlocked_by_tier_name() {
+        let tiers = unlocked(r#"["Rookie", "Kindergarten"]"#);
+        assert!(tiers.on("Rookie"));
+        assert!(tiers.on("Kindergarten"));
+        assert!(!tiers.on("Cadet"));
+        assert!(unlocked("true").on("Anything"));
+        assert!(!unlocked("false").on("Rookie"));
+
+        // older files go by the built in tiers' order
+        let flags = unlocked("[false, true, false, true]");
+        assert!(!flags.on("Rookie"));
+        assert!(flags.on("Cadet"));
+        assert!(!flags.on("Veteran"));
+        assert!(flags.on("Space Marine"));
+        assert!(flags.on("Kindergarten"));
+    }
+
+    #[test]
+    fn unknown_tiers_are_reported() {
+        let mut levels = Level::new();
+        levels[1].unlocked = unlocked(r#"["Rookie", "Kindergarten"]"#);
+        let errors = Level::validate_tiers(&levels, &Difficulty::new());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "levels[1].unlocked[1]");
+    }
+}
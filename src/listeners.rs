@@ -0,0 +1,258 @@
+use ggez::audio::SoundSource;
+use ggez::graphics;
+use ggez::Context;
+use std::collections::VecDeque;
+
+use crate::analytics::*;
+use crate::assets::*;
+use crate::endless::*;
+use crate::events::*;
+use crate::message::*;
+use crate::profile::*;
+use crate::score::*;
+use crate::simulation::*;
+
+/// What a listener may touch while handling an event
+pub struct EventContext<'a> {
+    pub ctx: &'a mut Context,
+    pub assets: &'a mut Assets,
+    pub messages: &'a mut VecDeque<Message>,
+    pub profile: &'a mut Profile,
+    pub high_scores: &'a mut HighScores,
+    pub sim: &'a Simulation,
+}
+
+impl<'a> EventContext<'a> {
+    pub fn push_message(&mut self, text: String) {
+        self.messages
+            .push_back(Message::new(text, 2000.0, self.assets, self.ctx));
+    }
+}
+
+/// Reacts to game events, add one to MainState's listeners to hook new
+/// behavior into the game without touching the Simulation.
+pub trait GameListener {
+    fn on_event(&mut self, event: &GameEvent, context: &mut EventContext);
+}
+
+pub fn default_listeners() -> Vec<Box<dyn GameListener>> {
+    vec![
+        Box::new(AudioListener),
+        Box::new(MessageListener),
+        Box::new(BackgroundListener),
+        Box::new(AnalyticsListener),
+        Box::new(HighScoreListener),
+        Box::new(AchievementListener::default()),
+    ]
+}
+
+pub struct AudioListener;
+
+impl GameListener for AudioListener {
+    fn on_event(&mut self, event: &GameEvent, context: &mut EventContext) {
+        let assets = &mut context.assets;
+        let sound = match event {
+            GameEvent::AlienDestroyed(_) => &mut assets.explosion_sound,
+            GameEvent::BossHit(_) => &mut assets.laser_sound,
+            GameEvent::WrongAnswer => &mut assets.fail_sound,
+            GameEvent::LevelCompleted { .. } => &mut assets.clap_sound,
+            GameEvent::WarpStarted { .. } => &mut assets.launch_sound,
+            _ => return,
+        };
+        let _ = sound.play_detached();
+    }
+}
+
+pub struct MessageListener;
+
+impl GameListener for MessageListener {
+    fn on_event(&mut self, event: &GameEvent, context: &mut EventContext) {
+        match event {
+            GameEvent::LevelStarted { level } => {
+                let title = context.sim.levels[*level].title.clone();
+                context.push_message(title);
+                context.push_message("Wave 1".to_string());
+            }
+            GameEvent::PracticeStarted => context.push_message("Practice".to_string()),
+            GameEvent::MadMinuteStarted => {
+                context.push_message("Mad Minute".to_string());
+                context.push_message("Go!".to_string());
+            }
+            GameEvent::EndlessStarted => {
+                context.push_message("Endless".to_string());
+                context.push_message("Wave 1".to_string());
+            }
+            GameEvent::WarpStarted { level } => {
+                let title = context.sim.levels[*level].title.clone();
+                context.push_message(title);
+                context.push_message("WARP SPEED".to_string());
+            }
+            GameEvent::WaveCleared { wave } => {
+                context.push_message("Wave Eliminated!".to_string());
+                context.push_message("Wave ".to_string() + &(wave + 2).to_string());
+            }
+            GameEvent::LifeLost { wave, lives_left } => {
+                if *lives_left > 0 {
+                    context.push_message(lives_left.to_string() + " Gun Left");
+                } else {
+                    context.push_message("Final Gun! Good Luck!".to_string());
+                }
+                context.push_message("Restarting Wave ".to_string() + &(wave + 1).to_string());
+            }
+            _ => (),
+        }
+    }
+}
+
+pub struct BackgroundListener;
+
+impl GameListener for BackgroundListener {
+    fn on_event(&mut self, event: &GameEvent, context: &mut EventContext) {
+        if let GameEvent::LevelStarted { level } = event {
+            context.assets.background = graphics::Image::new(
+                context.ctx,
+                context.sim.levels[*level].background_file.clone(),
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Keeps the profile's stats, progress, mastery and answer log
+pub struct AnalyticsListener;
+
+impl GameListener for AnalyticsListener {
+    fn on_event(&mut self, event: &GameEvent, context: &mut EventContext) {
+        let sim = context.sim;
+        let profile = &mut context.profile;
+        match event {
+            GameEvent::LevelStarted { .. } => profile.stats.levels_started += 1,
+            GameEvent::AlienDestroyed(record) => {
+                profile.mastery = sim.mastery.clone();
+                profile.stats.aliens_destroyed += 1;
+                append_record(&profile.dir(), record);
+            }
+            GameEvent::AlienReachedBottom(record) | GameEvent::BossHit(record) => {
+                profile.mastery = sim.mastery.clone();
+                append_record(&profile.dir(), record);
+            }
+            GameEvent::WrongAnswer => profile.stats.wrong_answers += 1,
+            GameEvent::WaveCleared { .. } => profile.save(),
+            GameEvent::LevelCompleted {
+                level,
+                unlocked,
+                score,
+            } => {
+                let level_id = sim.levels[*level].id();
+                let difficulty = sim.difficulty_tier().name;
+                profile
+                    .progress
+                    .record_completion(&level_id, &difficulty, Some(*score));
+                profile.stats.levels_completed += 1;
+                //unlock the next level
+                if let Some(next) = unlocked {
+                    let next_id = sim.levels[*next].id();
+                    profile.progress.unlock(&next_id, &difficulty);
+                }
+                profile.save();
+            }
+            GameEvent::LifeLost { .. } | GameEvent::GameOver => {
+                profile.stats.lives_lost += 1;
+                profile.save();
+            }
+            _ => (),
+        }
+    }
+}
+
+pub struct HighScoreListener;
+
+impl GameListener for HighScoreListener {
+    fn on_event(&mut self, event: &GameEvent, context: &mut EventContext) {
+        let (level_id, entry) = match event {
+            GameEvent::LevelCompleted { level, score, .. } => (
+                context.sim.levels[*level].id(),
+                HighScore::new(&context.profile.name, *score),
+            ),
+            // endless runs share one leaderboard per difficulty
+            GameEvent::EndlessFinished {
+                waves,
+                time_ms,
+                score,
+            } => {
+                let mut entry = HighScore::new(&context.profile.name, *score);
+                entry.waves = Some(*waves);
+                entry.seconds = Some(time_ms / 1000);
+                (ENDLESS_ID.to_string(), entry)
+            }
+            _ => return,
+        };
+        let difficulty = context.sim.difficulty_tier().name;
+        let place = context.high_scores.add(&level_id, &difficulty, entry);
+        if let Some(place) = place {
+            context.high_scores.save();
+            if place == 0 {
+                context.push_message("New High Score!".to_string());
+            }
+        }
+    }
+}
+
+pub const ACHIEVEMENT_FIRST_STRIKE: &str = "First Strike";
+pub const ACHIEVEMENT_SHARPSHOOTER: &str = "Sharpshooter";
+pub const ACHIEVEMENT_FLAWLESS_WAVE: &str = "Flawless Wave";
+pub const ACHIEVEMENT_GALAXY_SAVED: &str = "Galaxy Saved";
+// right on the first try this many times in a row
+const SHARPSHOOTER_STREAK: u32 = 10;
+
+#[derive(Default)]
+pub struct AchievementListener {
+    streak: u32,
+    wave_mistakes: u32,
+}
+
+impl AchievementListener {
+    fn award(&self, name: &str, context: &mut EventContext) {
+        if context.profile.achievements.iter().any(|a| a == name) {
+            return;
+        }
+        context.profile.achievements.push(name.to_string());
+        context.profile.save();
+        context.push_message("Achievement: ".to_string() + name);
+    }
+}
+
+impl GameListener for AchievementListener {
+    fn on_event(&mut self, event: &GameEvent, context: &mut EventContext) {
+        match event {
+            GameEvent::LevelStarted { .. } => {
+                self.streak = 0;
+                self.wave_mistakes = 0;
+            }
+            GameEvent::AlienDestroyed(record) | GameEvent::BossHit(record) => {
+                if let GameEvent::AlienDestroyed(_) = event {
+                    self.award(ACHIEVEMENT_FIRST_STRIKE, context);
+                }
+                if record.wrong_attempts() == 0 {
+                    self.streak += 1;
+                    if self.streak >= SHARPSHOOTER_STREAK {
+                        self.award(ACHIEVEMENT_SHARPSHOOTER, context);
+                    }
+                }
+            }
+            GameEvent::WrongAnswer | GameEvent::AlienReachedBottom(_) => {
+                self.streak = 0;
+                self.wave_mistakes += 1;
+            }
+            GameEvent::LifeLost { .. } => self.wave_mistakes = 0,
+            GameEvent::WaveCleared { .. } | GameEvent::LevelCompleted { .. } => {
+                if self.wave_mistakes == 0 {
+                    self.award(ACHIEVEMENT_FLAWLESS_WAVE, context);
+                }
+                self.wave_mistakes = 0;
+            }
+            GameEvent::GameWon => self.award(ACHIEVEMENT_GALAXY_SAVED, context),
+            _ => (),
+        }
+    }
+}
@@ -15,139 +15,184 @@ use std::env;
 use std::path;
 
 mod alien;
+mod analytics;
+mod answer;
 mod assets;
 mod background;
+mod bot;
+mod cli;
 mod crosshair;
+mod data_file;
+mod difficulty;
+mod endless;
+mod events;
 mod explosion;
+mod expression;
+mod facts;
 mod ggez_utility;
+mod integer_problems;
 mod level;
+mod listeners;
+mod mastery;
 mod mbtext;
 mod message;
+mod number_sense;
+mod pack;
+mod practice;
+mod problem;
+mod profile;
+mod progress;
+mod rational;
+mod score;
+mod simulation;
 mod turret;
 
 use crate::alien::*;
 use crate::assets::*;
 use crate::background::*;
 use crate::crosshair::*;
-use crate::explosion::*;
+use crate::difficulty::*;
+use crate::endless::*;
+use crate::events::*;
 use crate::ggez_utility::*;
 use crate::level::*;
+use crate::listeners::*;
 use crate::mbtext::*;
 use crate::message::*;
+use crate::pack::*;
+use crate::practice::*;
+use crate::profile::*;
+use crate::score::*;
+use crate::simulation::*;
 use crate::turret::*;
 
-fn get_lowest_living_alien(aliens: &Vec<Alien>) -> Option<usize> {
-    match aliens
+fn profile_name_texts(profiles: &[Profile], assets: &Assets, ctx: &mut Context) -> Vec<MBText> {
+    profiles
         .iter()
-        .enumerate()
-        .filter(|(_, alien)| alien.state != AlienState::Dead)
-        .max_by_key(|(_, alien)| (alien.pos[1] * 1000.0) as i32)
-    {
-        Some((index, _)) => Some(index),
-        None => None,
+        .map(|profile| profile.name.clone())
+        .chain(std::iter::once("New Profile".to_string()))
+        .map(|name| MBText::new_blink(name, &assets.main_font, WHITE, GRAY, 64.0, ctx))
+        .collect()
+}
+
+// difficulty names shrink past this many so the menu still fits
+const DIFFICULTY_MENU_ROWS: usize = 4;
+
+fn difficulty_name_texts(
+    difficulties: &[Difficulty],
+    assets: &Assets,
+    ctx: &mut Context,
+) -> Vec<MBText> {
+    let rows = difficulties.len().max(DIFFICULTY_MENU_ROWS);
+    let size = 64.0 * DIFFICULTY_MENU_ROWS as f32 / rows as f32;
+    difficulties
+        .iter()
+        .map(|d| MBText::new_blink(d.name.clone(), &assets.main_font, WHITE, GRAY, size, ctx))
+        .collect()
+}
+
+fn level_name_texts(levels: &[Level], assets: &Assets, ctx: &mut Context) -> Vec<MBText> {
+    levels
+        .iter()
+        .map(|level| level.title.clone())
+        .map(|title| MBText::new_blink(title, &assets.main_font, WHITE, GRAY, 64.0, ctx))
+        .collect()
+}
+
+fn pack_name_texts(packs: &[Pack], assets: &Assets, ctx: &mut Context) -> Vec<MBText> {
+    packs
+        .iter()
+        .map(|pack| pack.name.clone())
+        .map(|name| MBText::new_blink(name, &assets.main_font, WHITE, GRAY, 64.0, ctx))
+        .collect()
+}
+
+// grade and author on one line, the description under it
+fn pack_detail_texts(pack: &Pack, assets: &Assets, ctx: &mut Context) -> Vec<MBText> {
+    let mut header = pack.grade.clone();
+    if !pack.author.is_empty() {
+        if !header.is_empty() {
+            header += "  -  ";
+        }
+        header += &format!("by {}", pack.author);
     }
+    vec![header, pack.description.clone()]
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| MBText::new(line, &assets.main_font, WHITE, 36.0, ctx))
+        .collect()
 }
 
-fn gen_aliens(wave: &Wave, assets: &Assets, difficulty: usize) -> Vec<Alien> {
-    let mut aliens: Vec<Alien> = Vec::new();
-    let mut rng = rand::thread_rng();
-    for group in &wave.groups {
-        let alien_img = match group.operation {
-            Operation::Add => &assets.add_ship,
-            Operation::Subtract => &assets.sub_ship,
-            Operation::Multiply => &assets.mul_ship,
-            Operation::Divide => &assets.div_ship,
-        };
-        let alien_img_width = alien_img.width() as f32;
-        let alien_img_height = alien_img.height() as f32;
-        let num_ships = (group.num_ships as f32 * NUM_SHIPS_DIFFICULTY[difficulty]) as i32;
-        for i in 0..num_ships {
-            let min_number = (group.min_number as f32 * MIN_NUMBER_DIFFICULTY[difficulty]) as i32;
-            let max_number = (group.max_number as f32 * MAX_NUMBER_DIFFICULTY[difficulty]) as i32;
-
-            let (mut num1, mut num2) = if group.operation == Operation::Divide {
-                let mut a;
-                let mut b;
-                loop {
-                    a = rng.gen_range(min_number, max_number);
-                    b = if a == group.min_number {
-                        a
-                    } else {
-                        rng.gen_range(min_number, a)
-                    };
-                    if b == 0 {
-                        continue;
-                    }
-                    if a % b == 0 {
-                        break;
-                    }
-                }
-                (a, b)
-            } else {
-                (
-                    rng.gen_range(min_number, max_number),
-                    rng.gen_range(min_number, max_number),
-                )
-            };
+fn adaptive_text(adaptive: bool, assets: &Assets, ctx: &mut Context) -> MBText {
+    let text = if adaptive {
+        "Adaptive Mode: On  (press A)"
+    } else {
+        "Adaptive Mode: Off  (press A)"
+    };
+    MBText::new(text.to_string(), &assets.main_font, WHITE, 48.0, ctx)
+}
 
-            // make subtraction never negative until 3rd difficulty level
-            if difficulty < 2 && group.operation == Operation::Subtract {
-                let t = num2;
-                if num2 > num1 {
-                    num2 = num1;
-                    num1 = t;
-                }
-            }
+const MODE_NAMES: [&str; 4] = ["Campaign", "Practice", "Endless", "Mad Minute"];
+// rows of the practice setup menu after one per operation
+const PRACTICE_SMALLEST_ROW: usize = PRACTICE_OPERATIONS.len();
+const PRACTICE_LARGEST_ROW: usize = PRACTICE_OPERATIONS.len() + 1;
+const PRACTICE_START_ROW: usize = PRACTICE_OPERATIONS.len() + 2;
 
-            let (answer, op) = match group.operation {
-                Operation::Add => (num1 + num2, "+"),
-                Operation::Subtract => (num1 - num2, "-"),
-                Operation::Multiply => (num1 * num2, "X"),
-                Operation::Divide => (num1 / num2, "/"),
+fn practice_setup_texts(settings: &PracticeSettings, assets: &Assets, ctx: &mut Context) -> Vec<MBText> {
+    PRACTICE_OPERATIONS
+        .iter()
+        .map(|operation| {
+            let on = if settings.operations.contains(operation) {
+                "On"
+            } else {
+                "Off"
             };
-            let text = num1.to_string() + op + &num2.to_string();
-
-            // generate an x coordinate for aliens, make
-            // sure it isn't too close to aliens at nearby
-            // y so they don't overlap
-            let mut x: f32 = rng.gen_range(0.05, 0.95);
-            while aliens
-                .iter()
-                .rev()
-                .take(3)
-                .any(|alien| (alien.pos[0] - x).abs() < 0.1)
-            {
-                x = rng.gen_range(0.05, 0.95);
-            }
+            format!("{:?}  {}", operation, on)
+        })
+        .chain(vec![
+            format!("From  {}", settings.smallest),
+            format!("To  {}", settings.largest),
+            "Start".to_string(),
+        ])
+        .map(|line| MBText::new_blink(line, &assets.main_font, WHITE, GRAY, 64.0, ctx))
+        .collect()
+}
 
-            let alien = Alien {
-                operation: group.operation,
-                speed: group.speed as f32 * SPEED_DIFFICULTY[difficulty],
-                pos: na::Point2::new(x, -(i as i32) as f32 * 0.3),
-                text: graphics::Text::new((text, assets.number_font, 24.0)),
-                answer: answer,
-                explosion: Explosion::new(0.0, na::Point2::new(0.0, 0.0)),
-                state: AlienState::Alive,
-                src_pixel_width: alien_img_width,
-                src_pixel_height: alien_img_height,
-            };
-            aliens.push(alien);
-        }
+fn practice_summary_texts(summary: &PracticeSummary, assets: &Assets, ctx: &mut Context) -> Vec<MBText> {
+    let mut lines = vec![
+        format!("Correct  {}", summary.correct),
+        format!("Wrong Answers  {}", summary.wrong_attempts),
+        format!("Right First Try  {}%", summary.accuracy()),
+        format!("Average Time  {:.1}s", summary.average_ms() as f32 / 1000.0),
+    ];
+    // a few facts per line, enough to see what to work on
+    if !summary.missed.is_empty() {
+        lines.push("Missed".to_string());
+    }
+    for facts in summary.missed.chunks(6).take(3) {
+        lines.push(facts.join("   "));
     }
-    aliens.sort_by(|a, b| a.pos[0].partial_cmp(&b.pos[0]).unwrap());
-    aliens
+    lines
+        .into_iter()
+        .map(|line| MBText::new(line, &assets.main_font, WHITE, 48.0, ctx))
+        .collect()
 }
 
-#[derive(Debug, PartialEq)]
-enum GameState {
-    DifficultySelect,
-    LevelSelect,
-    LevelComplete,
-    LevelTransition(f32),
-    Playing,
-    Dying,
-    Dead,
-    Won,
+// a list of menu choices below the title, the selected one bright
+fn draw_choices(choices: &[MBText], selection: usize, ctx: &mut Context) {
+    let window_dimension = graphics::size(ctx);
+    let mut y = 0.4 * window_dimension.1 as f32;
+    for (i, choice) in choices.iter().enumerate() {
+        let vertical_size = choice.dest_pixel_dimensions(window_dimension).1;
+        let mut center = choice.center(ctx);
+        center[1] = y;
+        if i == selection {
+            choice.draw(center, ctx);
+        } else {
+            choice.draw_color(center, GRAY, ctx);
+        }
+        y += vertical_size * 1.075;
+    }
 }
 
 struct TextState {
@@ -157,25 +202,54 @@ struct TextState {
     math_title: MBText,
     level_complete: MBText,
     level_names: Vec<MBText>,
+    pack_names: Vec<MBText>,
+    // the selected pack's grade, author and description
+    pack_details: Vec<MBText>,
     difficulty_names: Vec<MBText>,
+    adaptive: MBText,
+    profile_names: Vec<MBText>,
+    new_profile_name: MBText,
+    // the last completed level's score and high score table
+    high_scores: Vec<MBText>,
+    mode_names: Vec<MBText>,
+    practice_setup: Vec<MBText>,
+    practice_complete: MBText,
+    practice_summary: Vec<MBText>,
+    times_up: MBText,
+    // seconds left in a mad minute
+    countdown: MBText,
 }
 struct MainState {
     messages: VecDeque<Message>,
     dt: std::time::Duration,
-    aliens: Vec<Alien>,
     assets: Assets,
-    levels: Vec<Level>,
-    current_level: usize,
-    current_wave: usize,
-    turret: Turret,
-    target: Option<usize>,
+    sim: Simulation,
+    listeners: Vec<Box<dyn GameListener>>,
+    profiles: Vec<Profile>,
+    current_profile: usize,
     background: Background,
-    state: GameState,
     text: TextState,
-    lives: usize,
+    // rebuilt whenever the turret's typed answer changes
+    turret_text: MBText,
+    turret_shown: (String, bool),
+    score_text: MBText,
+    score_shown: (u32, u32),
+    // one per alien, rebuilt when its problem changes
+    alien_texts: Vec<AlienText>,
+    high_scores: HighScores,
     crosshair: Crosshair,
+    // the campaign first, then the packs in PACK_DIR
+    packs: Vec<Pack>,
+    pack_selection: usize,
     level_selection: usize,
     difficulty_selection: usize,
+    mode_selection: usize,
+    practice_selection: usize,
+    // practice and mad minutes share the setup screen
+    setup_mode: GameMode,
+    countdown_shown: u64,
+    profile_selection: usize,
+    new_profile_name: Option<String>,
     up_key: Option<KeyCode>,
 }
 
@@ -183,14 +257,20 @@ impl MainState {
     fn new(ctx: &mut Context) -> GameResult<MainState> {
         let levels = Level::load_from_file();
         println!("levels count:{}",levels.len());
+        let packs = Pack::load_all(levels.clone());
+        let difficulties = Difficulty::load_from_file();
+        // set MATH_DEFENSE_SEED to replay a session exactly, every logged
+        // answer has the seed its session used
+        let seed = match env::var("MATH_DEFENSE_SEED") {
+            Ok(seed) => seed.parse::<u64>().unwrap_or_else(|_| rand::thread_rng().gen()),
+            Err(_) => rand::thread_rng().gen(),
+        };
         let assets = Assets::new(ctx, levels[0].background_file.clone());
         let messages = VecDeque::new();
-        let aliens = Vec::new();
-        let target = get_lowest_living_alien(&aliens);
+        let profiles = Profile::load_all();
 
         Ok(MainState {
             messages: messages,
-            aliens: aliens,
             text: TextState {
                 dead_text: MBText::new(
                     "You  Have  Died".to_string(),
@@ -221,20 +301,21 @@ impl MainState {
                     128.0,
                     ctx,
                 ),
-                level_names: levels
-                    .iter()
-                    .map(|level| {
-                        MBText::new_blink(
-                            level.title.clone(),
-                            &assets.main_font,
-                            WHITE,
-                            GRAY,
-                            64.0,
-                            ctx,
-                        )
-                    })
-                    .collect(),
-                difficulty_names: DIFFICULTY_NAMES
+                level_names: level_name_texts(&levels, &assets, ctx),
+                pack_names: pack_name_texts(&packs, &assets, ctx),
+                pack_details: pack_detail_texts(&packs[0], &assets, ctx),
+                difficulty_names: difficulty_name_texts(&difficulties, &assets, ctx),
+                adaptive: adaptive_text(false, &assets, ctx),
+                profile_names: profile_name_texts(&profiles, &assets, ctx),
+                new_profile_name: MBText::new(
+                    "Name: _".to_string(),
+                    &assets.main_font,
+                    WHITE,
+                    64.0,
+                    ctx,
+                ),
+                high_scores: Vec::new(),
+                mode_names: MODE_NAMES
                     .iter()
                     .map(|name| {
                         MBText::new_blink(
@@ -247,90 +328,175 @@ impl MainState {
                         )
                     })
                     .collect(),
+                practice_setup: Vec::new(),
+                practice_complete: MBText::new(
+                    "Practice  Complete".to_string(),
+                    &assets.title_font,
+                    BLUE,
+                    128.0,
+                    ctx,
+                ),
+                practice_summary: Vec::new(),
+                times_up: MBText::new(
+                    "Time's  Up!".to_string(),
+                    &assets.title_font,
+                    BLUE,
+                    128.0,
+                    ctx,
+                ),
+                countdown: MBText::new("".to_string(), &assets.number_font, WHITE, 48.0, ctx),
             },
-            turret: Turret::new(&assets, ctx),
-            levels: levels,
-            current_level: 0,
-            current_wave: 0,
-            target: target,
+            turret_text: MBText::new("".to_string(), &assets.number_font, WHITE, 24.0, ctx),
+            turret_shown: ("".to_string(), false),
+            score_text: MBText::new("0".to_string(), &assets.number_font, WHITE, 24.0, ctx),
+            score_shown: (0, 1),
+            alien_texts: Vec::new(),
+            high_scores: HighScores::load(),
+            sim: Simulation::new(levels, difficulties, seed),
+            listeners: default_listeners(),
+            profiles,
+            current_profile: 0,
             background: Background {
                 src_pixel_width: assets.background.width() as f32,
                 src_pixel_height: assets.background.height() as f32,
                 stars1_pos: 0.0,
                 stars2_pos: 0.0,
             },
-            state: GameState::DifficultySelect,
             dt: std::time::Duration::new(0, 0),
-            lives: 2,
             crosshair: Crosshair {
                 elapsed: 0,
                 src_pixel_width: assets.crosshair.width() as f32,
                 src_pixel_height: assets.crosshair.height() as f32,
             },
             assets: assets,
+            packs,
+            pack_selection: 0,
             level_selection: 0,
             difficulty_selection: 0,
+            mode_selection: 0,
+            practice_selection: 0,
+            setup_mode: GameMode::Practice,
+            countdown_shown: 0,
+            profile_selection: 0,
+            new_profile_name: None,
             up_key: None,
         })
     }
 
-    fn load_level_wave(&mut self, level: usize, wave: usize) {
-        self.current_level = level;
-        self.current_wave = wave;
-        self.target = None;
-        let wave = &self.levels[self.current_level].waves[self.current_wave];
-        self.aliens = gen_aliens(wave, &self.assets, self.difficulty_selection);
-        self.target = get_lowest_living_alien(&self.aliens);
+    fn profile(&self) -> &Profile {
+        &self.profiles[self.current_profile]
+    }
+
+    fn profile_mut(&mut self) -> &mut Profile {
+        &mut self.profiles[self.current_profile]
+    }
+
+    // the tier highlighted in the difficulty menu
+    fn difficulty_name(&self) -> &str {
+        &self.sim.difficulties[self.difficulty_selection].name
+    }
+
+    fn update_new_profile_text(&mut self, ctx: &mut Context) {
+        let name = self.new_profile_name.clone().unwrap_or_default();
+        self.text.new_profile_name = MBText::new(
+            "Name: ".to_string() + &name + "_",
+            &self.assets.main_font,
+            WHITE,
+            64.0,
+            ctx,
+        );
     }
 
-    fn set_level_wave(&mut self, level: usize, wave: usize) {
-        if level > self.current_level {
-            self.state = GameState::LevelComplete;
-            let _ = self.assets.clap_sound.play_detached();
+    fn create_profile(&mut self, ctx: &mut Context) {
+        let name = self.new_profile_name.clone().unwrap_or_default().trim().to_string();
+        if !Profile::is_new_name(&name, &self.profiles) {
+            let _ = self.assets.fail_sound.play_detached();
+            return;
         }
-        self.load_level_wave(level, wave);
+        let profile = Profile::new(name.clone());
+        profile.save();
+        self.profiles.push(profile);
+        self.profiles.sort_by_key(|profile| profile.name.to_lowercase());
+        self.text.profile_names = profile_name_texts(&self.profiles, &self.assets, ctx);
+        self.new_profile_name = None;
+        let index = self.profiles.iter().position(|p| p.name == name).unwrap();
+        self.select_profile(index, ctx);
     }
-    fn increment_level_wave(&mut self, ctx: &mut Context) {
-        //if we were at the last wave already then go to next level
-        if self.current_wave + 1 >= self.levels[self.current_level].waves.len() {            
-            if self.current_level + 1 >= self.levels.len() {
-                self.state = GameState::Won;
-                let _ = self.assets.clap_sound.play_detached();
+
+    fn select_profile(&mut self, index: usize, ctx: &mut Context) {
+        self.current_profile = index;
+        self.profile_selection = index;
+        // profiles from before the difficulty was saved start on the first tier
+        self.difficulty_selection = self
+            .sim
+            .difficulties
+            .iter()
+            .position(|difficulty| difficulty.name == self.profile().difficulty)
+            .unwrap_or(0);
+        self.text.adaptive = adaptive_text(self.profile().adaptive, &self.assets, ctx);
+        self.level_selection = 0;
+        self.sim.state = GameState::DifficultySelect;
+    }
+
+    fn update_profile_select(&mut self, ctx: &mut Context) {
+        for profile_name in &mut self.text.profile_names {
+            profile_name.update(self.dt);
+        }
+        self.text.new_profile_name.update(self.dt);
+        if let Some(keycode) = self.up_key {
+            if self.new_profile_name.is_some() {
+                if keycode == KeyCode::Return {
+                    self.create_profile(ctx);
+                } else if keycode == KeyCode::Back {
+                    if let Some(name) = &mut self.new_profile_name {
+                        let _ = name.pop();
+                    }
+                    self.update_new_profile_text(ctx);
+                }
             } else {
-                //unlock the next level and save the json
-                self.levels[self.current_level + 1].unlocked[self.difficulty_selection] = true;
-                Level::save_levels(&self.levels);
-                self.set_level_wave(self.current_level + 1, 0)
+                // the last entry is "New Profile"
+                let count = self.profiles.len() + 1;
+                if keycode == KeyCode::Return {
+                    if self.profile_selection == self.profiles.len() {
+                        self.new_profile_name = Some("".to_string());
+                        self.update_new_profile_text(ctx);
+                    } else {
+                        self.select_profile(self.profile_selection, ctx);
+                    }
+                } else if keycode == KeyCode::Down {
+                    self.profile_selection = (self.profile_selection + 1) % count;
+                } else if keycode == KeyCode::Up {
+                    self.profile_selection = if self.profile_selection == 0 {
+                        count - 1
+                    } else {
+                        self.profile_selection - 1
+                    };
+                }
             }
-        } else {
-            self.set_level_wave(self.current_level, self.current_wave + 1);
-            self.messages.push_back(Message::new(
-                "Wave Eliminated!".to_string(),
-                2000.0,
-                &self.assets,
-                ctx,
-            ));
-            self.messages.push_back(Message::new(
-                "Wave ".to_string() + &(self.current_wave + 1).to_string(),
-                2000.0,
-                &self.assets,
-                ctx,
-            ));
-        }
-    }
-    fn update_difficulty_select(&mut self, _ctx: &mut Context) {
+        }
+    }
+
+    fn update_difficulty_select(&mut self, ctx: &mut Context) {
         for difficulty in &mut self.text.difficulty_names {
             difficulty.update(self.dt);
         }
         if let Some(keycode) = self.up_key {
             if keycode == KeyCode::Return {
-                self.state = GameState::LevelSelect;
+                self.profile_mut().difficulty = self.difficulty_name().to_string();
+                self.profile().save();
+                self.sim.state = GameState::ModeSelect;
+            } else if keycode == KeyCode::A {
+                // adaptive mode replaces the difficulty speed multiplier
+                let adaptive = !self.profile().adaptive;
+                self.profile_mut().adaptive = adaptive;
+                self.profile().save();
+                self.text.adaptive = adaptive_text(adaptive, &self.assets, ctx);
             } else if keycode == KeyCode::Down {
                 self.difficulty_selection =
-                    (self.difficulty_selection + 1) % DIFFICULTY_NAMES.len();
+                    (self.difficulty_selection + 1) % self.sim.difficulties.len();
             } else if keycode == KeyCode::Up {
                 self.difficulty_selection = if self.difficulty_selection == 0 {
-                    DIFFICULTY_NAMES.len() - 1
+                    self.sim.difficulties.len() - 1
                 } else {
                     self.difficulty_selection - 1
                 };
@@ -338,285 +504,325 @@ impl MainState {
         }
     }
 
-    fn update_level_select(&mut self, ctx: &mut Context) {
-        let unlocked_count = self
-            .levels
-            .iter()
-            .filter(|level| level.unlocked[self.difficulty_selection])
-            .count();
+    fn update_mode_select(&mut self, ctx: &mut Context) {
+        for mode_name in &mut self.text.mode_names {
+            mode_name.update(self.dt);
+        }
         if let Some(keycode) = self.up_key {
             if keycode == KeyCode::Return {
-                self.load_level_wave(self.level_selection, 0);
-                self.assets.background = graphics::Image::new(
-                    ctx,
-                    self.levels[self.current_level].background_file.clone(),
-                )
-                .unwrap();
-                self.messages.push_back(Message::new(
-                    self.levels[self.level_selection].title.clone(),
-                    2000.0,
-                    &self.assets,
-                    ctx,
-                ));
-                self.messages.push_back(Message::new(
-                    "Wave 1".to_string(),
-                    2000.0,
-                    &self.assets,
-                    ctx,
-                ));
-                self.lives = 2;
-                self.turret = Turret::new(&self.assets, ctx);
-                self.state = GameState::Playing;
+                match MODE_NAMES[self.mode_selection] {
+                    "Practice" | "Mad Minute" => {
+                        self.setup_mode = if MODE_NAMES[self.mode_selection] == "Practice" {
+                            GameMode::Practice
+                        } else {
+                            GameMode::MadMinute
+                        };
+                        self.practice_selection = PRACTICE_START_ROW;
+                        self.text.practice_setup =
+                            practice_setup_texts(&self.profile().practice, &self.assets, ctx);
+                        self.sim.state = GameState::PracticeSetup;
+                    }
+                    "Endless" => {
+                        self.sim.difficulty = self.difficulty_selection;
+                        self.sim.adaptive = self.profile().adaptive;
+                        self.sim.mastery = self.profile().mastery.clone();
+                        self.sim.start_endless();
+                    }
+                    _ => self.sim.state = GameState::PackSelect,
+                }
             } else if keycode == KeyCode::Down {
-                self.level_selection = (self.level_selection + 1) % unlocked_count;
+                self.mode_selection = (self.mode_selection + 1) % MODE_NAMES.len();
             } else if keycode == KeyCode::Up {
-                self.level_selection = if self.level_selection == 0 {
-                    unlocked_count - 1
+                self.mode_selection = if self.mode_selection == 0 {
+                    MODE_NAMES.len() - 1
                 } else {
-                    self.level_selection - 1
+                    self.mode_selection - 1
                 };
             }
         }
-        for level_name in &mut self.text.level_names {
-            level_name.update(self.dt)
-        }
     }
-    fn update_won(&mut self, _ctx: &mut Context) {
+
+    fn update_pack_select(&mut self, ctx: &mut Context) {
+        for pack_name in &mut self.text.pack_names {
+            pack_name.update(self.dt);
+        }
         if let Some(keycode) = self.up_key {
             if keycode == KeyCode::Return {
-                self.state = GameState::DifficultySelect;
+                // the simulation only ever plays the chosen pack's levels
+                let levels = self.packs[self.pack_selection].levels.clone();
+                self.text.level_names = level_name_texts(&levels, &self.assets, ctx);
+                self.sim.levels = levels;
+                self.level_selection = 0;
+                self.sim.state = GameState::LevelSelect;
+                return;
+            } else if keycode == KeyCode::Down {
+                self.pack_selection = (self.pack_selection + 1) % self.packs.len();
+            } else if keycode == KeyCode::Up {
+                self.pack_selection = if self.pack_selection == 0 {
+                    self.packs.len() - 1
+                } else {
+                    self.pack_selection - 1
+                };
             }
+            self.text.pack_details =
+                pack_detail_texts(&self.packs[self.pack_selection], &self.assets, ctx);
         }
     }
-    fn update_level_complete(&mut self, ctx: &mut Context) {
-        self.turret.rotation = 0.0;
-        self.background.update(self.dt, 1.0);
-        if let Some(keycode) = self.up_key {
-            if keycode == KeyCode::Return {
-                let _ = self.assets.launch_sound.play_detached();
-                self.state = GameState::LevelTransition(0.0);
-                self.levels[self.current_level].push_title(&mut self.messages, &self.assets, ctx);
-                self.messages.push_back(Message::new(
-                    "WARP SPEED".to_string(),
-                    2000.0,
-                    &self.assets,
-                    ctx,
-                ));
+
+    fn update_practice_setup(&mut self, ctx: &mut Context) {
+        for line in &mut self.text.practice_setup {
+            line.update(self.dt);
+        }
+        let keycode = match self.up_key {
+            Some(keycode) => keycode,
+            None => return,
+        };
+        let row = self.practice_selection;
+        let settings = &mut self.profiles[self.current_profile].practice;
+        if keycode == KeyCode::Return && row == PRACTICE_START_ROW {
+            self.profile().save();
+            // practice plays at the chosen numbers, not the difficulty's
+            self.sim.difficulty = 0;
+            self.sim.adaptive = false;
+            self.sim.mastery = self.profile().mastery.clone();
+            let settings = self.profile().practice.clone();
+            if self.setup_mode == GameMode::MadMinute {
+                self.sim.start_mad_minute(&settings);
+            } else {
+                self.sim.start_practice(&settings);
             }
+            return;
+        } else if keycode == KeyCode::Return && row < PRACTICE_SMALLEST_ROW {
+            settings.toggle(PRACTICE_OPERATIONS[row]);
+        } else if keycode == KeyCode::Left && row == PRACTICE_SMALLEST_ROW {
+            settings.smallest = (settings.smallest - 1).max(0);
+        } else if keycode == KeyCode::Right && row == PRACTICE_SMALLEST_ROW {
+            settings.smallest = (settings.smallest + 1).min(settings.largest);
+        } else if keycode == KeyCode::Left && row == PRACTICE_LARGEST_ROW {
+            settings.largest = (settings.largest - 1).max(settings.smallest.max(1));
+        } else if keycode == KeyCode::Right && row == PRACTICE_LARGEST_ROW {
+            settings.largest = (settings.largest + 1).min(PRACTICE_MAX_NUMBER);
+        } else if keycode == KeyCode::Down {
+            self.practice_selection = (row + 1) % (PRACTICE_START_ROW + 1);
+        } else if keycode == KeyCode::Up {
+            self.practice_selection = if row == 0 { PRACTICE_START_ROW } else { row - 1 };
         }
+        self.text.practice_setup = practice_setup_texts(&self.profile().practice, &self.assets, ctx);
     }
-    fn update_level_transition(&mut self, ctx: &mut Context, elapsed: f32) {
-        self.state = GameState::LevelTransition(elapsed + self.dt.as_millis() as f32);
-        let pct = elapsed / 3000.0;
-        self.background.update(self.dt, 1.0 + pct * 1.0);
-        self.turret.pos[1] -= 0.015 * pct;
-
-        if elapsed >= 3000.0 {
-            self.state = GameState::Playing;
-            self.levels[self.current_level].push_title(&mut self.messages, &self.assets, ctx);
-            self.messages.push_back(Message::new(
-                "Wave 1".to_string(),
-                2000.0,
-                &self.assets,
-                ctx,
-            ));
-            self.assets.background =
-                graphics::Image::new(ctx, self.levels[self.current_level].background_file.clone())
-                    .unwrap();
-            self.turret.pos = na::Point2::new(0.5, 0.9); //put turret back at the bottom
-        }
-    }
-
-    fn update_dead(&mut self, _ctx: &mut Context) {
+
+    fn update_practice_summary(&mut self) {
         if let Some(keycode) = self.up_key {
             if keycode == KeyCode::Return {
-                self.state = GameState::LevelSelect;
+                self.sim.state = GameState::ModeSelect;
             }
         }
     }
 
-    fn update_dying(&mut self, ctx: &mut Context) {
-        for alien in &mut self.aliens {
-            alien.update(&mut self.turret, ctx, self.dt);
-        }
-        self.turret.update(ctx, self.dt);
-        for splosion in &mut self.turret.explosions {
-            splosion.update(ctx, self.dt);
+    fn update_level_select(&mut self) {
+        let progress = &self.profile().progress;
+        let difficulty = self.difficulty_name();
+        // packs can unlock any levels, not just the first few
+        let unlocked: Vec<usize> = (0..self.sim.levels.len())
+            .filter(|i| progress.is_unlocked(&self.sim.levels[*i], difficulty))
+            .collect();
+        // nothing to pick if a level file locks every level, Escape still goes back
+        if unlocked.is_empty() {
+            return;
         }
-        if self
-            .turret
-            .explosions
+        let position = unlocked
             .iter()
-            .all(|splosion| splosion.elapsed - splosion.start_time > splosion.duration)
-        {
-            if self.lives > 0 {
-                self.lives -= 1;
-                self.turret = Turret::new(&self.assets, ctx);
-                self.set_level_wave(self.current_level, self.current_wave);
-                self.state = GameState::Playing;
-                if self.lives > 0 {
-                    self.messages.push_back(Message::new(
-                        self.lives.to_string() + &" Gun Left".to_string(),
-                        2000.0,
-                        &self.assets,
-                        ctx,
-                    ));
-                } else {
-                    self.messages.push_back(Message::new(
-                        "Final Gun! Good Luck!".to_string(),
-                        2000.0,
-                        &self.assets,
-                        ctx,
-                    ));
+            .position(|i| *i == self.level_selection)
+            .unwrap_or(0);
+        self.level_selection = unlocked[position];
+        if let Some(keycode) = self.up_key {
+            if keycode == KeyCode::Return {
+                // the simulation plays with a copy of the profile's settings
+                self.sim.difficulty = self.difficulty_selection;
+                self.sim.adaptive = self.profile().adaptive;
+                self.sim.mastery = self.profile().mastery.clone();
+                self.sim.start_level(self.level_selection);
+            } else if keycode == KeyCode::Down {
+                self.level_selection = unlocked[(position + 1) % unlocked.len()];
+            } else if keycode == KeyCode::Up {
+                self.level_selection = unlocked[(position + unlocked.len() - 1) % unlocked.len()];
+            }
+        }
+        for level_name in &mut self.text.level_names {
+            level_name.update(self.dt)
+        }
+    }
+    fn update_game(&mut self, ctx: &mut Context) {
+        match self.sim.state {
+            GameState::Playing => {
+                self.background.update(self.dt, 1.0);
+                self.crosshair.update(self.dt);
+                if !self.messages.is_empty() {
+                    self.messages[0].update(self.dt);
+                    if self.messages[0].elapsed >= self.messages[0].duration {
+                        let _ = self.messages.pop_front();
+                    }
                 }
-                self.messages.push_back(Message::new(
-                    "Restarting Wave ".to_string() + &(self.current_wave + 1).to_string(),
-                    2000.0,
-                    &self.assets,
-                    ctx,
-                ));
-            } else {
-                self.state = GameState::Dead;
             }
+            GameState::LevelComplete => self.background.update(self.dt, 1.0),
+            GameState::LevelTransition(elapsed) => {
+                let pct = elapsed / 3000.0;
+                self.background.update(self.dt, 1.0 + pct * 1.0);
+            }
+            _ => (),
         }
+        self.sim.update(self.dt);
+        self.handle_events(ctx);
+        self.update_turret_text(ctx);
+        self.update_score_text(ctx);
+        self.update_countdown_text(ctx);
     }
 
-    fn update_playing(&mut self, ctx: &mut Context) {
-        self.background.update(self.dt, 1.0);
-        self.crosshair.update(self.dt);
-        if let Some(keycode) = self.up_key {
-            if keycode == KeyCode::Return {
-                match self.turret.raw_text.parse::<i32>() {
-                    Ok(n) => match self.target {
-                        Some(alien_index) if self.aliens[alien_index].answer == n => {
-                            self.aliens[alien_index].state = AlienState::Exploding;
-                            let _ = self.assets.explosion_sound.play_detached();
-                            self.turret.state = TurretState::Firing;
-                        }
-                        _ => {
-                            let _ = self.assets.fail_sound.play_detached();
-                        }
-                    },
-                    Err(_) => (),
+    // hand whatever the simulation reported to each listener
+    fn handle_events(&mut self, ctx: &mut Context) {
+        let events: Vec<GameEvent> = self.sim.events.drain(..).collect();
+        let mut context = EventContext {
+            ctx,
+            assets: &mut self.assets,
+            messages: &mut self.messages,
+            profile: &mut self.profiles[self.current_profile],
+            high_scores: &mut self.high_scores,
+            sim: &self.sim,
+        };
+        for event in &events {
+            for listener in &mut self.listeners {
+                listener.on_event(event, &mut context);
+            }
+        }
+        for event in &events {
+            match event {
+                GameEvent::LevelCompleted { level, score, .. } => {
+                    let level_id = self.sim.levels[*level].id();
+                    self.update_high_score_text(&level_id, *score, ctx)
                 }
-                self.turret.raw_text = "".to_string();
-                self.turret.text = MBText::new(
-                    self.turret.raw_text.clone(),
-                    &self.assets.number_font,
-                    WHITE,
-                    24.0,
-                    ctx,
-                );
-            } else if keycode == KeyCode::Back {
-                let _ = self.turret.raw_text.pop();
-                self.turret.text = MBText::new(
-                    self.turret.raw_text.clone(),
-                    &self.assets.number_font,
-                    WHITE,
-                    24.0,
-                    ctx,
-                );
-            } else if keycode == KeyCode::Left {
-                match self.target {
-                    Some(index) => {
-                        if self
-                            .aliens
-                            .iter()
-                            .any(|alien| alien.state == AlienState::Alive && alien.pos[1] >= 0.0)
-                        {
-                            let mut i = if index == 0 {
-                                self.aliens.len() - 1
-                            } else {
-                                index - 1
-                            };
-                            while self.aliens[i].state != AlienState::Alive
-                                || self.aliens[i].pos[1] < 0.0
-                            {
-                                i = if i == 0 { self.aliens.len() - 1 } else { i - 1 }
-                            }
-                            self.target = Some(i);
-                        }
-                    }
-                    _ => (),
+                GameEvent::EndlessFinished { score, .. } => {
+                    self.update_high_score_text(ENDLESS_ID, *score, ctx)
                 }
-            } else if keycode == KeyCode::Right {                
-                match self.target {
-                    Some(index) => {
-                        if self
-                            .aliens
-                            .iter()
-                            .any(|alien| alien.state == AlienState::Alive && alien.pos[1] > 0.0)
-                        {
-                            let mut i = (index + 1) % self.aliens.len();
-                            while self.aliens[i].state != AlienState::Alive
-                                || self.aliens[i].pos[1] < 0.0
-                            {
-                                i = (i + 1) % self.aliens.len();
-                            }
-                            self.target = Some(i);
-                        }
-                    }
-
-                    _ => (),
+                GameEvent::PracticeFinished(summary) | GameEvent::MadMinuteFinished(summary) => {
+                    self.text.practice_summary = practice_summary_texts(summary, &self.assets, ctx)
                 }
+                _ => (),
             }
         }
+    }
 
-        //update aliens and turret, and message queue
-        for alien in &mut self.aliens {
-            alien.update(&mut self.turret, ctx, self.dt);
+    fn update_alien_texts(&mut self) {
+        self.alien_texts.truncate(self.sim.aliens.len());
+        for (i, alien) in self.sim.aliens.iter().enumerate() {
+            if i == self.alien_texts.len() {
+                self.alien_texts.push(AlienText::new(alien, &self.assets));
+            } else if !self.alien_texts[i].is_for(alien) {
+                self.alien_texts[i] = AlienText::new(alien, &self.assets);
+            }
         }
-        self.turret.update(ctx, self.dt);
-        if !self.messages.is_empty() {
-            self.messages[0].update(self.dt);
-            if self.messages[0].elapsed >= self.messages[0].duration {
-                let _ = self.messages.pop_front();
+    }
+
+    fn update_score_text(&mut self, ctx: &mut Context) {
+        let shown = (self.sim.score.points, self.sim.score.multiplier());
+        if shown != self.score_shown {
+            self.score_shown = shown;
+            let mut text = shown.0.to_string();
+            if shown.1 > 1 {
+                text += &format!("  x{}", shown.1);
             }
+            self.score_text = MBText::new(text, &self.assets.number_font, WHITE, 24.0, ctx);
         }
+    }
 
-        // If there is a target, rotate the turret to it
-        match self.target {
-            Some(target) if self.aliens[target].state != AlienState::Dead => {
-                let turret_pos = self.turret.pct_pos();
-                let turret_vector: na::Vector2<f32> =
-                    na::Vector2::new(turret_pos[0], turret_pos[1]);
-                let alien_pos = self.aliens[target].pct_pos();
-                let alien_vector = na::Vector2::new(alien_pos[0], alien_pos[1]);
-                let v1 = na::Vector2::new(0.0, -1.0);
-                let v2 = alien_vector - turret_vector;
-                let mut angle = v2.angle(&v1);
-                if alien_pos[0] < 0.5 {
-                    angle = -angle;
+    fn update_countdown_text(&mut self, ctx: &mut Context) {
+        if self.sim.mode != GameMode::MadMinute {
+            return;
+        }
+        // round up so it reads 0:00 only when time is up
+        let seconds = self.sim.time_left_ms().div_ceil(1000);
+        if seconds != self.countdown_shown {
+            self.countdown_shown = seconds;
+            let text = format!("{}:{:02}", seconds / 60, seconds % 60);
+            let color = if seconds <= 10 { RED } else { WHITE };
+            self.text.countdown = MBText::new(text, &self.assets.number_font, color, 48.0, ctx);
+        }
+    }
+
+    fn update_high_score_text(&mut self, level_id: &str, score: u32, ctx: &mut Context) {
+        let difficulty = self.sim.difficulty_tier().name;
+        let table = self.high_scores.table(level_id, &difficulty);
+        let lines = std::iter::once(format!("Score  {}", score)).chain(
+            table.iter().enumerate().map(|(i, high_score)| {
+                let mut line = format!("{}.  {}  {}", i + 1, high_score.name, high_score.points);
+                if let (Some(waves), Some(seconds)) = (high_score.waves, high_score.seconds) {
+                    line += &format!("  {} waves  {}:{:02}", waves, seconds / 60, seconds % 60);
                 }
-                self.turret.rotation = angle;
+                line
+            }),
+        );
+        let font = self.assets.main_font;
+        self.text.high_scores = lines
+            .map(|line| MBText::new(line, &font, WHITE, 48.0, ctx))
+            .collect();
+    }
+
+    fn draw_high_scores(&self, ctx: &mut Context) {
+        let window_dimension = graphics::size(ctx);
+        let mut y = 0.6 * window_dimension.1;
+        for (i, line) in self.text.high_scores.iter().enumerate() {
+            let mut center = line.center(ctx);
+            center[1] = y;
+            if i == 0 {
+                line.draw_color(center, BLUE, ctx);
+            } else {
+                line.draw(center, ctx);
             }
-            Some(_) => self.target = get_lowest_living_alien(&self.aliens),
-            None => (),
+            y += line.dest_pixel_dimensions(window_dimension).1 * 1.075;
+        }
+    }
+
+    fn update_turret_text(&mut self, ctx: &mut Context) {
+        let turret = &self.sim.turret;
+        // a wrong answer flashes red in place of the typed text
+        let shown = if turret.showing_wrong_answer() {
+            (turret.wrong_text.clone(), true)
+        } else if turret.wrong_flash > 0.0 {
+            ("".to_string(), false)
+        } else {
+            (turret.raw_text.clone(), false)
         };
+        if shown != self.turret_shown {
+            let color = if shown.1 { RED } else { WHITE };
+            self.turret_text = MBText::new(shown.0.clone(), &self.assets.number_font, color, 24.0, ctx);
+            self.turret_shown = shown;
+        }
+    }
 
-        // Find the alien furthest down the screen, if its at the bottom, dead.
-        match self
-            .aliens
-            .iter()
-            .max_by_key(|alien| (alien.pos[1] * 1000.0) as i32)
-        {
-            Some(alien) => {
-                if alien.pos[1] > 0.9 {                    
-                    self.state = GameState::Dying
-                };
-            }
-            None => (),
+    fn draw_profile_select(&mut self, ctx: &mut Context) {
+        self.background.draw_no_stars(ctx, &self.assets);
+        let mut title_pos = self.text.math_title.center(ctx);
+        title_pos[1] *= 0.5;
+        self.text.math_title.draw(title_pos, ctx);
+
+        if self.new_profile_name.is_some() {
+            self.text.new_profile_name.draw_center(ctx);
+            return;
         }
 
-        //If all aliens are dead, increment the wave/level
-        if self
-            .aliens
-            .iter()
-            .all(|alien| alien.state == AlienState::Dead)
-        {
-            self.increment_level_wave(ctx);
+        let window_dimension = graphics::size(ctx);
+        let mut y = 0.4 * window_dimension.1 as f32;
+        for (i, profile_name) in self.text.profile_names.iter().enumerate() {
+            let vertical_size = profile_name.dest_pixel_dimensions(window_dimension).1;
+            let mut center = profile_name.center(ctx);
+            center[1] = y;
+            if i == self.profile_selection {
+                profile_name.draw(center, ctx);
+            } else {
+                profile_name.draw_color(center, GRAY, ctx);
+            }
+            y += vertical_size * 1.075;
         }
     }
+
     fn draw_difficulty_select(&mut self, ctx: &mut Context) {
         self.background.draw_no_stars(ctx, &self.assets);
         let mut title_pos = self.text.math_title.center(ctx);
@@ -637,6 +843,61 @@ impl MainState {
             }
             y += vertical_size * 1.075;
         }
+        let mut center = self.text.adaptive.center(ctx);
+        center[1] = y + 0.05 * window_dimension.1;
+        self.text.adaptive.draw(center, ctx);
+    }
+
+    fn draw_title(&mut self, ctx: &mut Context) {
+        self.background.draw_no_stars(ctx, &self.assets);
+        let mut title_pos = self.text.math_title.center(ctx);
+        title_pos[1] *= 0.5;
+        self.text.math_title.draw(title_pos, ctx);
+    }
+
+    fn draw_mode_select(&mut self, ctx: &mut Context) {
+        self.draw_title(ctx);
+        draw_choices(&self.text.mode_names, self.mode_selection, ctx);
+    }
+
+    fn draw_pack_select(&mut self, ctx: &mut Context) {
+        self.draw_title(ctx);
+        draw_choices(&self.text.pack_names, self.pack_selection, ctx);
+        let window_dimension = graphics::size(ctx);
+        let mut y = 0.8 * window_dimension.1;
+        for line in &self.text.pack_details {
+            let mut center = line.center(ctx);
+            center[1] = y;
+            line.draw(center, ctx);
+            y += line.dest_pixel_dimensions(window_dimension).1 * 1.075;
+        }
+    }
+
+    fn draw_practice_setup(&mut self, ctx: &mut Context) {
+        self.draw_title(ctx);
+        draw_choices(&self.text.practice_setup, self.practice_selection, ctx);
+    }
+
+    fn draw_practice_summary(&mut self, ctx: &mut Context) {
+        self.background.draw_no_stars(ctx, &self.assets);
+        let title = if self.sim.mode == GameMode::MadMinute {
+            &self.text.times_up
+        } else {
+            &self.text.practice_complete
+        };
+        let mut title_pos = title.center(ctx);
+        title_pos[1] *= 0.4;
+        title.draw(title_pos, ctx);
+
+        let window_dimension = graphics::size(ctx);
+        let mut y = 0.35 * window_dimension.1;
+        for line in &self.text.practice_summary {
+            let mut center = line.center(ctx);
+            center[1] = y;
+            line.draw(center, ctx);
+            y += line.dest_pixel_dimensions(window_dimension).1 * 1.075;
+        }
+        self.text.press_enter.draw_horizontal_center(0.85 * window_dimension.1, ctx);
     }
 
     fn draw_level_select(&mut self, ctx: &mut Context) {
@@ -653,7 +914,11 @@ impl MainState {
             center[1] = y;
             if i == self.level_selection {
                 level_name.draw(center, ctx);
-            } else if self.levels[i].unlocked[self.difficulty_selection] {
+            } else if self
+                .profile()
+                .progress
+                .is_unlocked(&self.sim.levels[i], self.difficulty_name())
+            {
                 level_name.draw_color(center, GRAY, ctx);
             } else {
                 level_name.draw_color(center, DARK_GRAY, ctx);
@@ -667,6 +932,7 @@ impl MainState {
         title_pos[1] *= 0.5;
         self.text.press_enter.draw_center(ctx);
         self.text.won_text.draw(title_pos, ctx);
+        self.draw_high_scores(ctx);
     }
 
     fn draw_level_complete(&mut self, ctx: &mut Context) {
@@ -675,8 +941,10 @@ impl MainState {
         title_pos[1] *= 0.5;
         self.text.press_enter.draw_center(ctx);
         self.text.level_complete.draw(title_pos, ctx);
-        self.turret.draw(ctx, &mut self.assets);
-        self.turret.draw_lives(self.lives, ctx, &mut self.assets);
+        self.draw_high_scores(ctx);
+        self.sim.turret.draw(&self.turret_text, ctx, &mut self.assets);
+        self.sim.turret.draw_lives(self.sim.lives, ctx, &mut self.assets);
+        self.sim.turret.draw_score(&self.score_text, ctx);
     }
 
     fn draw_level_transition(&mut self, ctx: &mut Context, elapsed: f32) {
@@ -685,8 +953,9 @@ impl MainState {
         title_pos[1] *= 0.5;
         self.text.press_enter.draw_center(ctx);
         self.text.level_complete.draw(title_pos, ctx);
-        self.turret.draw(ctx, &mut self.assets);
-        self.turret.draw_lives(self.lives, ctx, &mut self.assets);
+        self.sim.turret.draw(&self.turret_text, ctx, &mut self.assets);
+        self.sim.turret.draw_lives(self.sim.lives, ctx, &mut self.assets);
+        self.sim.turret.draw_score(&self.score_text, ctx);
         let pct = elapsed / 3000.0;
         if pct > 0.75 {
             let r = ((elapsed * 2.0) as i32 % 255) as u8;
@@ -702,25 +971,28 @@ impl MainState {
         title_pos[1] *= 0.5;
         self.text.press_enter.draw_center(ctx);
         self.text.dead_text.draw(title_pos, ctx);
+        if self.sim.mode == GameMode::Endless {
+            self.draw_high_scores(ctx);
+        }
     }
     fn draw_playing(&mut self, ctx: &mut Context) {
         //Draw the background
         self.background.draw(ctx, &self.assets);
 
         // if we have a target, draw the crosshair
-        match self.target {
+        match self.sim.target {
             Some(target) => {
-                let alien = &self.aliens[target];
+                let alien = &self.sim.aliens[target];
 
                 //draw the crosshair on the target
                 let crosshair_pos =
                     to_screen_pos((alien.pos[0], alien.pos[1]), graphics::size(ctx));
                 self.crosshair.draw(crosshair_pos, ctx, &self.assets);
                 //draw the laser if the turret is firing
-                match self.turret.state {
+                match self.sim.turret.state {
                     TurretState::Firing => {
                         let screen_size = graphics::size(ctx);
-                        let turret_pos = self.turret.pixel_pos(screen_size);
+                        let turret_pos = self.sim.turret.pixel_pos(screen_size);
 
                         //make the lasers come out of the actual gunscar
                         let left_pos = na::Point2::new(turret_pos[0] - 0.01*screen_size.0,turret_pos[1] - 0.01*screen_size.1);
@@ -758,45 +1030,50 @@ impl MainState {
         };
 
         //draw the aliens, turrets, and messages
-        for alien in &mut self.aliens {
-            alien.draw(ctx, &mut self.assets);
+        self.update_alien_texts();
+        for (alien, text) in self.sim.aliens.iter_mut().zip(&self.alien_texts) {
+            alien.draw(&text.text, ctx, &mut self.assets);
+        }
+        self.sim.turret.draw(&self.turret_text, ctx, &mut self.assets);
+        self.sim.turret.draw_lives(self.sim.lives, ctx, &mut self.assets);
+        self.sim.turret.draw_score(&self.score_text, ctx);
+        if self.sim.mode == GameMode::MadMinute {
+            let window_dimension = graphics::size(ctx);
+            self.text.countdown.draw_horizontal_center(0.06 * window_dimension.1, ctx);
         }
-        self.turret.draw(ctx, &mut self.assets);
-        self.turret.draw_lives(self.lives, ctx, &mut self.assets);
         if !self.messages.is_empty() {
             self.messages[0].draw(ctx);
         }
     }
     fn draw_dying(&mut self, ctx: &mut Context) {
         self.background.draw(ctx, &self.assets);
-        for alien in &mut self.aliens {
-            alien.draw(ctx, &mut self.assets);
+        self.update_alien_texts();
+        for (alien, text) in self.sim.aliens.iter_mut().zip(&self.alien_texts) {
+            alien.draw(&text.text, ctx, &mut self.assets);
         }
-        self.turret.draw(ctx, &mut self.assets);
-        self.turret.draw_lives(self.lives, ctx, &mut self.assets);
-        for i in 0..self.turret.explosions.len() {
-            let mut pos = self.turret.pixel_pos(graphics::size(ctx));
+        self.sim.turret.draw(&self.turret_text, ctx, &mut self.assets);
+        self.sim.turret.draw_lives(self.sim.lives, ctx, &mut self.assets);
+        self.sim.turret.draw_score(&self.score_text, ctx);
+        for i in 0..self.sim.turret.explosions.len() {
+            let mut pos = self.sim.turret.pixel_pos(graphics::size(ctx));
             pos[0] += ((10 + i % 2) as f32 / 100.0) * graphics::size(ctx).0;
 
-            self.turret.explosions[i].draw(ctx, &mut self.assets)
+            self.sim.turret.explosions[i].draw(ctx, &mut self.assets)
         }
     }
 }
 impl event::EventHandler for MainState {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
         self.dt = timer::delta(ctx);
-        match &self.state {
+        match &self.sim.state {
+            GameState::ProfileSelect => self.update_profile_select(ctx),
             GameState::DifficultySelect => self.update_difficulty_select(ctx),
-            GameState::LevelSelect => self.update_level_select(ctx),
-            GameState::LevelTransition(elapsed) => {
-                let x = *elapsed;
-                self.update_level_transition(ctx, x);
-            }
-            GameState::Playing => self.update_playing(ctx),
-            GameState::Dying => self.update_dying(ctx),
-            GameState::Dead => self.update_dead(ctx),
-            GameState::Won => self.update_won(ctx),
-            GameState::LevelComplete => self.update_level_complete(ctx),
+            GameState::ModeSelect => self.update_mode_select(ctx),
+            GameState::PackSelect => self.update_pack_select(ctx),
+            GameState::LevelSelect => self.update_level_select(),
+            GameState::PracticeSetup => self.update_practice_setup(ctx),
+            GameState::PracticeSummary => self.update_practice_summary(),
+            _ => self.update_game(ctx),
         }
         //clear out the up key event, now that the update funcs have had a chance to see it
         match self.up_key {
@@ -808,9 +1085,14 @@ impl event::EventHandler for MainState {
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
-        match &mut self.state {
+        match &mut self.sim.state {
+            GameState::ProfileSelect => self.draw_profile_select(ctx),
             GameState::DifficultySelect => self.draw_difficulty_select(ctx),
+            GameState::ModeSelect => self.draw_mode_select(ctx),
+            GameState::PackSelect => self.draw_pack_select(ctx),
             GameState::LevelSelect => self.draw_level_select(ctx),
+            GameState::PracticeSetup => self.draw_practice_setup(ctx),
+            GameState::PracticeSummary => self.draw_practice_summary(ctx),
             GameState::LevelTransition(elapsed) => {
                 let x = *elapsed;
                 self.draw_level_transition(ctx, x);
@@ -831,17 +1113,16 @@ impl event::EventHandler for MainState {
     }
 
     fn text_input_event(&mut self, ctx: &mut Context, ch: char) {
-        if self.state == GameState::Playing {
-            if ('0' <= ch && ch <= '9') || ch == '-' {
-                self.turret.raw_text += &ch.to_string();
-                self.turret.text = MBText::new(
-                    self.turret.raw_text.clone(),
-                    &self.assets.number_font,
-                    WHITE,
-                    24.0,
-                    ctx,
-                );
+        if self.sim.state == GameState::ProfileSelect {
+            if let Some(name) = &mut self.new_profile_name {
+                if (ch.is_alphanumeric() || ch == ' ') && name.chars().count() < MAX_NAME_LENGTH {
+                    name.push(ch);
+                    self.update_new_profile_text(ctx);
+                }
             }
+        } else {
+            self.sim.text_input(ch);
+            self.update_turret_text(ctx);
         }
     }
 
@@ -857,17 +1138,46 @@ impl event::EventHandler for MainState {
 
     fn key_up_event(&mut self, ctx: &mut Context, keycode: KeyCode, _keymod: KeyMods) {
         match keycode {
-            KeyCode::Escape => match self.state {
-                GameState::LevelSelect => self.state = GameState::DifficultySelect,
-                GameState::DifficultySelect => event::quit(ctx),
-                _ => self.state = GameState::LevelSelect,
+            KeyCode::Escape => match self.sim.state {
+                GameState::ModeSelect => self.sim.state = GameState::DifficultySelect,
+                GameState::PackSelect
+                | GameState::PracticeSetup
+                | GameState::PracticeSummary => self.sim.state = GameState::ModeSelect,
+                GameState::LevelSelect => self.sim.state = GameState::PackSelect,
+                GameState::DifficultySelect => self.sim.state = GameState::ProfileSelect,
+                GameState::ProfileSelect => {
+                    if self.new_profile_name.is_some() {
+                        self.new_profile_name = None;
+                    } else {
+                        event::quit(ctx)
+                    }
+                }
+                _ if self.sim.mode == GameMode::Practice || self.sim.mode == GameMode::MadMinute => {
+                    self.sim.finish_practice();
+                    self.handle_events(ctx);
+                }
+                _ if self.sim.mode == GameMode::Endless => {
+                    // dying already finished the run
+                    if self.sim.state != GameState::Dead {
+                        self.sim.finish_endless();
+                        self.handle_events(ctx);
+                    }
+                    self.sim.state = GameState::ModeSelect;
+                }
+                _ => self.sim.state = GameState::LevelSelect,
             },
-            _ => self.up_key = Some(keycode),
+            _ if self.sim.state.is_menu() => self.up_key = Some(keycode),
+            _ => self.sim.key_up(keycode),
         }
     }
 }
 
 pub fn main() -> GameResult {
+    let args: Vec<String> = env::args().collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
         let mut path = path::PathBuf::from(manifest_dir);
         path.push("resources");
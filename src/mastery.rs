@@ -0,0 +1,161 @@
+use crate::analytics::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// answers slower than this count against a fact
+pub const TARGET_ANSWER_MS: f32 = 4000.0;
+pub const MIN_ADAPTIVE_SPEED: f32 = 0.6;
+pub const MAX_ADAPTIVE_SPEED: f32 = 1.6;
+
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct FactMastery {
+    pub seen: u32,
+    // answered right on the first try
+    pub first_try: u32,
+    pub average_ms: f32,
+}
+
+/// How well a player knows each fact, used by adaptive mode to pick
+/// problems and set alien speed. Facts are keyed by problem text, "7X8".
+#[derive(Deserialize, Serialize, Clone)]
+pub struct MasteryModel {
+    pub facts: BTreeMap<String, FactMastery>,
+    // multiplies wave speed in adaptive mode, nudged after every answer
+    pub speed: f32,
+}
+
+impl Default for MasteryModel {
+    fn default() -> MasteryModel {
+        MasteryModel {
+            facts: BTreeMap::new(),
+            speed: 1.0,
+        }
+    }
+}
+
+impl MasteryModel {
+    pub fn record(&mut self, record: &AnswerRecord) {
+        let first_try = record.outcome == Outcome::Correct && record.wrong_attempts() == 0;
+        let fact = self
+            .facts
+            .entry(record.problem.clone())
+            .or_default();
+        fact.seen += 1;
+        if first_try {
+            fact.first_try += 1;
+        }
+        // running average that leans on recent answers
+        let time = record.time_ms as f32;
+        fact.average_ms = if fact.seen == 1 {
+            time
+        } else {
+            fact.average_ms * 0.7 + time * 0.3
+        };
+
+        // speed up for quick correct answers, back off for misses
+        if record.outcome == Outcome::ReachedBottom {
+            self.speed -= 0.1;
+        } else if first_try && time < TARGET_ANSWER_MS {
+            self.speed += 0.03;
+        }
+        self.speed -= 0.03 * record.wrong_attempts() as f32;
+        self.speed = self.speed.clamp(MIN_ADAPTIVE_SPEED, MAX_ADAPTIVE_SPEED);
+    }
+
+    /// How much a fact needs practice, facts never seen sit in the middle
+    pub fn weight(&self, fact: &str) -> f32 {
+        match self.facts.get(fact) {
+            Some(mastery) if mastery.seen > 0 => {
+                let accuracy = mastery.first_try as f32 / mastery.seen as f32;
+                let slowness = (mastery.average_ms / TARGET_ANSWER_MS).min(3.0);
+                0.25 + (1.0 - accuracy) * 3.0 + slowness
+            }
+            _ => 1.5,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::answer::*;
+    use crate::difficulty::*;
+    use crate::level::*;
+    use crate::problem::*;
+    use crate::rational::*;
+
+    fn record(problem: &str, attempts: &[&str], time_ms: u32, outcome: Outcome) -> AnswerRecord {
+        AnswerRecord {
+            session: 0,
+            seed: 0,
+            timestamp: 0,
+            level_id: String::new(),
+            wave: 0,
+            difficulty: String::new(),
+            operation: Operation::Add,
+            operands: Vec::new(),
+            problem: problem.to_string(),
+            answer: Answer::Number(Rational::zero()),
+            attempts: attempts.iter().map(|attempt| attempt.to_string()).collect(),
+            time_ms,
+            outcome,
+        }
+    }
+
+    #[test]
+    fn speed_stays_within_bounds() {
+        let mut mastery = MasteryModel::default();
+        for _ in 0..100 {
+            mastery.record(&record("1+1", &["2"], 1000, Outcome::Correct));
+            assert!(mastery.speed <= MAX_ADAPTIVE_SPEED);
+        }
+        assert_eq!(mastery.speed, MAX_ADAPTIVE_SPEED);
+        for _ in 0..100 {
+            mastery.record(&record("1+1", &["3", "4"], 9000, Outcome::ReachedBottom));
+            assert!(mastery.speed >= MIN_ADAPTIVE_SPEED);
+        }
+        assert_eq!(mastery.speed, MIN_ADAPTIVE_SPEED);
+    }
+
+    #[test]
+    fn weights_follow_answers() {
+        let mut mastery = MasteryModel::default();
+        mastery.record(&record("1+1", &["2"], 1000, Outcome::Correct));
+        mastery.record(&record("7X8", &["54", "56"], 8000, Outcome::Correct));
+        assert!(mastery.weight("1+1") < mastery.weight("never seen"));
+        assert!(mastery.weight("7X8") > mastery.weight("never seen"));
+    }
+
+    #[test]
+    fn adaptive_favors_heavier_facts() {
+        let group = WaveGroup {
+            operation: Operation::Add,
+            speed: 1.0,
+            num_ships: 1,
+            min_number: 1,
+            max_number: 3,
+            ..Default::default()
+        };
+        let struggling = Problem::new(Operation::Add, 2, 2).unwrap().display_text;
+        let mut mastery = MasteryModel::default();
+        for (a, b) in [(1, 1), (1, 2), (2, 1)].iter() {
+            let known = Problem::new(Operation::Add, *a, *b).unwrap().display_text;
+            mastery.record(&record(&known, &["x"], 1000, Outcome::Correct));
+        }
+        mastery.record(&record(&struggling, &["5", "3", "4"], 9000, Outcome::Correct));
+
+        let mut generator = ProblemGenerator::from_seed(1);
+        let draws = 400;
+        let picked = (0..draws)
+            .filter(|_| {
+                generator
+                    .generate_adaptive(&group, &Difficulty::default(), &mastery)
+                    .display_text
+                    == struggling
+            })
+            .count();
+        // a quarter of the problems without adaptive mode
+        assert!(picked > draws / 2, "{} of {}", picked, draws);
+    }
+}
@@ -100,7 +100,7 @@ impl MBText {
     }
 }
 
-impl Scalable for MBText {
+impl Positioned for MBText {
     fn pct_pos(&self) -> na::Point2<f32> {
         na::Point2::new(0.0, 0.0)
     }
@@ -108,7 +108,9 @@ impl Scalable for MBText {
     fn pct_dimensions(&self) -> (f32, f32) {
         (self.w, self.h)
     }
+}
 
+impl Scalable for MBText {
     fn src_pixel_dimensions(&self) -> (f32, f32) {
         (self.actual_w, self.actual_h)
     }
@@ -0,0 +1,150 @@
+use rand::*;
+
+use crate::answer::*;
+use crate::difficulty::*;
+use crate::level::*;
+use crate::problem::*;
+use crate::rational::*;
+
+// shown on yes or no questions, filled in with the answer when revealed
+pub const YES_NO_PROMPT: &str = "y/n";
+
+// what Round and PlaceValue ask about
+const PLACES: [(i32, &str); 4] =
+    [(1, "ones"), (10, "tens"), (100, "hundreds"), (1000, "thousands")];
+
+// random numbers tried looking for a prime or two different fractions
+const NUMBER_SENSE_TRIES: usize = 20;
+
+pub fn is_prime(n: i32) -> bool {
+    if n < 2 {
+        return false;
+    }
+    let mut divisor = 2;
+    while divisor * divisor <= n {
+        if n % divisor == 0 {
+            return false;
+        }
+        divisor += 1;
+    }
+    true
+}
+
+fn place_name(place: Rational) -> Option<&'static str> {
+    PLACES
+        .iter()
+        .find(|(value, _)| Rational::from(*value) == place)
+        .map(|(_, name)| *name)
+}
+
+/// Works the answer out from the operands, None if they don't make a
+/// question, like comparing 3/4 with itself
+pub fn number_sense_answer(operation: Operation, operands: &[Rational]) -> Option<Answer> {
+    match (operation, operands) {
+        (Operation::Compare, [a, b]) if a != b => Some(Answer::Number(*a.max(b))),
+        (Operation::Round, [n, place]) if n.is_integer() && place_name(*place).is_some() => {
+            // halves round up, 345 goes to 350
+            let rounded = (n.numer + place.numer / 2).div_euclid(place.numer) * place.numer;
+            Some(Answer::Number(Rational::from(rounded)))
+        }
+        (Operation::Prime, [n]) if n.is_integer() => Some(Answer::YesNo(is_prime(n.numer))),
+        (Operation::PlaceValue, [n, place]) if n.is_integer() && place_name(*place).is_some() => {
+            let digit = (n.numer.abs() / place.numer) % 10;
+            Some(Answer::Number(Rational::from(digit)))
+        }
+        _ => None,
+    }
+}
+
+impl Problem {
+    /// Compare takes two fractions, Round and PlaceValue a number and a
+    /// place like 10, Prime just the number
+    pub fn number_sense(operation: Operation, operands: Vec<Rational>) -> Problem {
+        let place = || operands.get(1).and_then(|place| place_name(*place)).unwrap_or("?");
+        let display_text = match operation {
+            Operation::Compare => format!("bigger: {} or {}", operands[0], operands[1]),
+            Operation::Round => format!("round {} to {}", operands[0], place()),
+            Operation::Prime => format!("is {} prime? {}", operands[0], YES_NO_PROMPT),
+            Operation::PlaceValue => format!("{} digit of {}", place(), operands[0]),
+            _ => panic!("{:?} is not a number sense problem", operation),
+        };
+        Problem {
+            answer: number_sense_answer(operation, &operands)
+                .unwrap_or_else(|| Answer::Number(Rational::zero())),
+            operands,
+            operation,
+            display_text,
+            expression: None,
+            missing: None,
+        }
+    }
+}
+
+impl<R: Rng> ProblemGenerator<R> {
+    pub fn generate_number_sense(&mut self, group: &WaveGroup, difficulty: &Difficulty) -> Problem {
+        let (min_number, max_number) =
+            scale_range(group.min_number, group.max_number, difficulty);
+        let operands = match group.operation {
+            Operation::Compare => {
+                // proper fractions with denominators from min_number..max_number,
+                // like Fraction problems
+                let mut fraction = || {
+                    let denom = self.rng.gen_range(min_number.max(2), max_number.max(3));
+                    Rational::new(self.rng.gen_range(1, denom), denom)
+                        .unwrap_or_else(|_| Rational::zero())
+                };
+                let mut pair = (Rational { numer: 1, denom: 2 }, Rational { numer: 1, denom: 3 });
+                for _ in 0..NUMBER_SENSE_TRIES {
+                    let (a, b) = (fraction(), fraction());
+                    if a != b {
+                        pair = (a, b);
+                        break;
+                    }
+                }
+                vec![pair.0, pair.1]
+            }
+            Operation::Round => {
+                let n = self.rng.gen_range(min_number, max_number);
+                // round to places below the biggest number, or to tens
+                let places: Vec<i32> = PLACES[1..]
+                    .iter()
+                    .map(|(place, _)| *place)
+                    .filter(|place| *place < max_number.max(min_number.abs()))
+                    .collect();
+                let place = if places.is_empty() {
+                    10
+                } else {
+                    places[self.rng.gen_range(0, places.len())]
+                };
+                vec![Rational::from(n), Rational::from(place)]
+            }
+            Operation::Prime => {
+                // about half should be primes, they are rarer than that
+                let mut n = self.rng.gen_range(min_number, max_number);
+                if self.rng.gen() {
+                    for _ in 0..NUMBER_SENSE_TRIES {
+                        if is_prime(n) {
+                            break;
+                        }
+                        n = self.rng.gen_range(min_number, max_number);
+                    }
+                }
+                vec![Rational::from(n)]
+            }
+            _ => {
+                // only places the number has, no hundreds digit of 47
+                let n = self.rng.gen_range(min_number, max_number);
+                let places: Vec<i32> = PLACES
+                    .iter()
+                    .map(|(place, _)| *place)
+                    .filter(|place| *place == 1 || *place <= n.abs())
+                    .collect();
+                let place = places[self.rng.gen_range(0, places.len())];
+                vec![Rational::from(n), Rational::from(place)]
+            }
+        };
+        let problem = Problem::number_sense(group.operation, operands);
+        debug_assert!(problem.is_valid(), "invalid problem {:?}", problem);
+        problem
+    }
+}
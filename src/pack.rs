@@ -0,0 +1,162 @@
+use crate::data_file::*;
+use crate::level::*;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PACK_DIR: &str = "resources/packs";
+// the levels from LEVEL_FILE, always the first pack
+pub const CAMPAIGN_PACK_ID: &str = "campaign";
+
+/// A set of levels for a class or topic, like "Times Tables", one file
+/// per pack in PACK_DIR. Level ids are prefixed with the pack id when it
+/// loads so progress and high scores are kept per pack.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Pack {
+    // falls back to the name when missing, like level ids
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    // who it's for, like "Grade 2"
+    #[serde(default)]
+    pub grade: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub description: String,
+    pub levels: Vec<Level>,
+}
+
+impl Pack {
+    pub fn id(&self) -> String {
+        if self.id.is_empty() {
+            slugify(&self.name)
+        } else {
+            self.id.clone()
+        }
+    }
+
+    /// The levels from LEVEL_FILE, their ids aren't prefixed so progress
+    /// from before packs still counts
+    pub fn campaign(levels: Vec<Level>) -> Pack {
+        Pack {
+            id: CAMPAIGN_PACK_ID.to_string(),
+            name: "Math Defense".to_string(),
+            grade: "All Grades".to_string(),
+            author: String::new(),
+            description: "The original campaign, from adding to the final assault".to_string(),
+            levels,
+        }
+    }
+
+    // addition-attack in the grade-2 pack becomes grade-2/addition-attack
+    fn scope_level_ids(mut self) -> Pack {
+        let id = self.id();
+        for level in &mut self.levels {
+            level.id = format!("{}/{}", id, level.id());
+        }
+        self
+    }
+
+    /// Checks the pack's details and levels, background files are looked
+    /// up in resource_dir
+    pub fn validate(&self, resource_dir: &Path) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if self.name.trim().is_empty() {
+            errors.push(ValidationError::new("name", "name is empty".to_string()));
+        }
+        if self.id() == CAMPAIGN_PACK_ID {
+            errors.push(ValidationError::new(
+                "id",
+                format!("\"{}\" is kept for {}", CAMPAIGN_PACK_ID, LEVEL_FILE),
+            ));
+        }
+        errors.extend(Level::validate_levels(&self.levels, resource_dir));
+        errors
+    }
+
+    /// Parses and validates a pack file, background files are looked up
+    /// in the resources directory above PACK_DIR
+    pub fn validate_file(path: &Path) -> Result<Pack, Vec<ValidationError>> {
+        let resource_dir = path
+            .parent()
+            .and_then(|dir| dir.parent())
+            .unwrap_or_else(|| Path::new("."));
+        read_validated(path, |pack: &Pack| pack.validate(resource_dir)).map(Pack::scope_level_ids)
+    }
+
+    /// Every .json file in PACK_DIR, sorted so packs show up in a fixed order
+    pub fn pack_files() -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = match fs::read_dir(PACK_DIR) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        files.sort();
+        files
+    }
+
+    /// The campaign followed by every pack in PACK_DIR that loads. Broken
+    /// packs are left out and their errors printed, like levels.
+    pub fn load_all(campaign: Vec<Level>) -> Vec<Pack> {
+        let mut packs = vec![Pack::campaign(campaign)];
+        for path in Pack::pack_files() {
+            match Pack::validate_file(&path) {
+                Ok(pack) => {
+                    if packs.iter().any(|other| other.id() == pack.id()) {
+                        println!(
+                            "Skipping pack {}, id {} is already used",
+                            path.display(),
+                            pack.id()
+                        );
+                    } else {
+                        packs.push(pack);
+                    }
+                }
+                Err(errors) => {
+                    println!("Error loading pack {}", path.display());
+                    for error in &errors {
+                        println!("  {}", error);
+                    }
+                }
+            }
+        }
+        packs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(id: &str) -> Pack {
+        Pack {
+            id: id.to_string(),
+            name: id.to_string(),
+            grade: String::new(),
+            author: String::new(),
+            description: String::new(),
+            levels: Level::new(),
+        }
+    }
+
+    #[test]
+    fn level_ids_are_scoped_per_pack() {
+        let campaign = Pack::campaign(Level::new());
+        let first = pack("grade-2").scope_level_ids();
+        let second = pack("grade-3").scope_level_ids();
+        assert_eq!(campaign.levels[0].id(), "addition-attack");
+        assert_eq!(first.levels[0].id(), "grade-2/addition-attack");
+        assert_eq!(second.levels[0].id(), "grade-3/addition-attack");
+
+        let named = Pack {
+            id: String::new(),
+            name: "Times Tables".to_string(),
+            ..pack("")
+        };
+        assert_eq!(named.scope_level_ids().levels[0].id(), "times-tables/addition-attack");
+    }
+}
@@ -0,0 +1,136 @@
+use crate::analytics::*;
+use crate::level::*;
+
+use serde::{Deserialize, Serialize};
+
+pub const PRACTICE_ID: &str = "practice";
+// aliens stop here in practice, above the turret line
+pub const PRACTICE_STOP_Y: f32 = 0.8;
+pub const PRACTICE_WAVES: usize = 3;
+pub const PRACTICE_SHIPS_PER_WAVE: usize = 6;
+pub const PRACTICE_MAX_NUMBER: i32 = 20;
+// a mad minute is a timed test over the practice settings
+pub const MAD_MINUTE_ID: &str = "madminute";
+pub const MAD_MINUTE_MS: u64 = 60000;
+pub const PRACTICE_OPERATIONS: [Operation; 4] = [
+    Operation::Add,
+    Operation::Subtract,
+    Operation::Multiply,
+    Operation::Divide,
+];
+
+/// What to drill in practice, kept with the profile so it is remembered
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PracticeSettings {
+    pub operations: Vec<Operation>,
+    // both inclusive, unlike WaveGroup's ranges
+    pub smallest: i32,
+    pub largest: i32,
+}
+
+impl Default for PracticeSettings {
+    fn default() -> PracticeSettings {
+        PracticeSettings {
+            operations: vec![Operation::Add],
+            smallest: 0,
+            largest: 10,
+        }
+    }
+}
+
+impl PracticeSettings {
+    pub fn toggle(&mut self, operation: Operation) {
+        if self.operations.contains(&operation) {
+            // always keep at least one
+            if self.operations.len() > 1 {
+                self.operations.retain(|op| *op != operation);
+            }
+        } else {
+            self.operations.push(operation);
+        }
+    }
+
+    /// A level of slow aliens built from the settings, for the Simulation
+    pub fn level(&self) -> Level {
+        let ships = (PRACTICE_SHIPS_PER_WAVE / self.operations.len()).max(2);
+        let wave = Wave {
+            groups: self
+                .operations
+                .iter()
+                .map(|operation| WaveGroup {
+                    operation: *operation,
+                    speed: 3.0,
+                    num_ships: ships,
+                    min_number: self.smallest,
+                    max_number: self.largest + 1,
+                    ..Default::default()
+                })
+                .collect(),
+        };
+        Level {
+            id: PRACTICE_ID.to_string(),
+            waves: vec![wave; PRACTICE_WAVES],
+            background_file: String::new(),
+            title: "Practice".to_string(),
+            unlocked: Unlocked::Everywhere(true),
+            penalty: PenaltyMode::None,
+            reveal_after: Some(2),
+        }
+    }
+
+    /// A single wave for a mad minute, the Simulation replaces each alien
+    /// as it dies so the wave never ends
+    pub fn mad_minute_level(&self) -> Level {
+        let mut level = self.level();
+        level.id = MAD_MINUTE_ID.to_string();
+        level.title = "Mad Minute".to_string();
+        level.waves.truncate(1);
+        for group in &mut level.waves[0].groups {
+            group.speed = 4.0;
+        }
+        // it's a test, so no help with the answers
+        level.reveal_after = None;
+        level
+    }
+}
+
+/// How a practice session or mad minute went, shown when it ends
+#[derive(Debug, Default, Clone)]
+pub struct PracticeSummary {
+    pub answered: u32,
+    pub correct: u32,
+    pub first_try: u32,
+    pub wrong_attempts: u32,
+    pub total_ms: u64,
+    // facts answered wrong or left to land, each listed once
+    pub missed: Vec<String>,
+}
+
+impl PracticeSummary {
+    pub fn record(&mut self, record: &AnswerRecord) {
+        self.answered += 1;
+        if record.outcome == Outcome::Correct {
+            self.correct += 1;
+        }
+        if record.outcome == Outcome::Correct && record.wrong_attempts() == 0 {
+            self.first_try += 1;
+        } else if !self.missed.contains(&record.problem) {
+            self.missed.push(record.problem.clone());
+        }
+        self.wrong_attempts += record.wrong_attempts() as u32;
+        self.total_ms += record.time_ms as u64;
+    }
+
+    // percent of aliens answered right the first time
+    pub fn accuracy(&self) -> u32 {
+        (self.first_try * 100).checked_div(self.answered).unwrap_or(0)
+    }
+
+    pub fn average_ms(&self) -> u64 {
+        if self.answered == 0 {
+            0
+        } else {
+            self.total_ms / self.answered as u64
+        }
+    }
+}
@@ -0,0 +1,595 @@
+use crate::answer::*;
+use crate::difficulty::*;
+use crate::expression::*;
+use crate::integer_problems::*;
+use crate::level::*;
+use crate::mastery::*;
+use crate::number_sense::*;
+use crate::rational::*;
+use rand::rngs::StdRng;
+use rand::*;
+use std::collections::{BTreeMap, VecDeque};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub operands: Vec<Rational>,
+    pub operation: Operation,
+    pub answer: Answer,
+    pub display_text: String,
+    // the whole tree for Expression, Fraction and Decimal problems,
+    // operands are its numbers
+    pub expression: Option<Expr>,
+    // the operand asked for instead of the result, it is also the answer
+    pub missing: Option<usize>,
+}
+
+impl Problem {
+    /// Add, Subtract, Multiply and Divide problems, an error when the
+    /// answer doesn't fit in an i32 or the division isn't even
+    pub fn new(operation: Operation, num1: i32, num2: i32) -> Result<Problem, String> {
+        let answer = match operation {
+            Operation::Add => num1.checked_add(num2),
+            Operation::Subtract => num1.checked_sub(num2),
+            Operation::Multiply => num1.checked_mul(num2),
+            Operation::Divide if num1.checked_rem(num2) == Some(0) => num1.checked_div(num2),
+            Operation::Divide => None,
+            _ => return Err(format!("{:?} problems aren't built from two numbers", operation)),
+        };
+        let answer = answer.ok_or_else(|| {
+            format!("{}{}{} has no whole answer that fits", num1, symbol(operation), num2)
+        })?;
+        Ok(Problem {
+            operands: vec![Rational::from(num1), Rational::from(num2)],
+            operation,
+            answer: Answer::Number(Rational::from(answer)),
+            display_text: number_text(Rational::from(num1), false, true)
+                + symbol(operation)
+                + &number_text(Rational::from(num2), false, false),
+            expression: None,
+            missing: None,
+        })
+    }
+
+    /// Asks for operands[index] instead of the result: 7X6 becomes ?X6=42
+    pub fn with_missing(&self, index: usize) -> Problem {
+        let mut texts: Vec<String> = (0..self.operands.len())
+            .map(|i| number_text(self.operands[i], false, i == 0))
+            .collect();
+        texts[index] = "?".to_string();
+        Problem {
+            operands: self.operands.clone(),
+            operation: self.operation,
+            answer: Answer::Number(self.operands[index]),
+            display_text: texts[0].clone()
+                + symbol(self.operation)
+                + &texts[1]
+                + "="
+                + &self.answer.to_string(),
+            expression: None,
+            missing: Some(index),
+        }
+    }
+
+    // only one number fits the gap, 0X?=0 could be anything
+    fn operand_is_unique(&self, index: usize) -> bool {
+        match (self.operation, self.result()) {
+            (_, None) => false,
+            (Operation::Multiply, _) => self.operands[1 - index] != Rational::zero(),
+            (Operation::Divide, Some(quotient)) => index == 0 || quotient != Rational::zero(),
+            _ => true,
+        }
+    }
+
+    pub fn from_expression(operation: Operation, expression: Expr) -> Problem {
+        Problem {
+            operands: expression.operands(),
+            operation,
+            answer: Answer::Number(expression.eval().unwrap_or_else(Rational::zero)),
+            display_text: expression.to_text(operation == Operation::Decimal),
+            expression: Some(expression),
+            missing: None,
+        }
+    }
+
+    /// How a value should be typed for this problem, 1.25 rather than 5/4
+    /// for decimals
+    pub fn format_answer(&self, value: Rational) -> String {
+        match self.operation {
+            Operation::Decimal => value.to_decimal().unwrap_or_else(|| value.to_string()),
+            _ => value.to_string(),
+        }
+    }
+
+    /// Whether typed is the answer. Any way of writing the number counts,
+    /// 0.75 or 6/8 for 3/4, except that Fraction problems want the
+    /// answer in lowest terms.
+    pub fn is_answer(&self, typed: &str) -> bool {
+        match typed.parse::<Answer>() {
+            Ok(answer) if answer == self.answer => {
+                self.operation != Operation::Fraction || in_lowest_terms(typed)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn answer_text(&self) -> String {
+        match self.answer {
+            Answer::Number(n) => self.format_answer(n),
+            _ => self.answer.to_string(),
+        }
+    }
+
+    // the problem with its answer filled in, shown after too many misses
+    pub fn revealed_text(&self) -> String {
+        match (self.missing, self.answer) {
+            (Some(index), Answer::Number(n)) => {
+                self.display_text.replacen('?', &number_text(n, false, index == 0), 1)
+            }
+            (_, Answer::YesNo(_)) => self.display_text.replace(YES_NO_PROMPT, &self.answer_text()),
+            (_, Answer::Remainder { .. }) => {
+                self.display_text.replace(REMAINDER_PROMPT, &self.answer_text())
+            }
+            (_, Answer::Number(_)) => format!("{}={}", self.display_text, self.answer_text()),
+        }
+    }
+
+    // the two operands combined, division must come out even
+    fn result(&self) -> Option<Rational> {
+        if self.operands.len() != 2 {
+            return None;
+        }
+        let (a, b) = (self.operands[0], self.operands[1]);
+        match self.operation {
+            Operation::Add => a.checked_add(b),
+            Operation::Subtract => a.checked_sub(b),
+            Operation::Multiply => a.checked_mul(b),
+            Operation::Divide => a.checked_div(b).filter(|n| n.is_integer()),
+            _ => None,
+        }
+    }
+
+    /// Recomputes the answer from the operands and checks it matches,
+    /// division must also come out even.
+    pub fn is_valid(&self) -> bool {
+        if let Some(expression) = &self.expression {
+            return expression.eval().map(Answer::Number) == Some(self.answer);
+        }
+        if self.operation.is_number_sense() {
+            return number_sense_answer(self.operation, &self.operands) == Some(self.answer);
+        }
+        if INTEGER_OPERATIONS.contains(&self.operation) {
+            return integer_answer(self.operation, &self.operands) == Some(self.answer);
+        }
+        match self.missing {
+            Some(index) => {
+                index < self.operands.len()
+                    && self.answer == Answer::Number(self.operands[index])
+                    && self.operand_is_unique(index)
+            }
+            None => self.result().map(Answer::Number) == Some(self.answer),
+        }
+    }
+}
+
+/// Generates problems for a WaveGroup. All randomness comes from the
+/// rng passed in, so a session can be replayed from the same seed.
+pub struct ProblemGenerator<R: Rng = StdRng> {
+    pub rng: R,
+    // operands of the facts each group asked lately, newest first. Groups
+    // are told apart by the facts they list, so a list used again in a
+    // later wave carries on where it left off.
+    pub recent_facts: BTreeMap<Vec<Vec<i32>>, VecDeque<Vec<i32>>>,
+}
+
+impl ProblemGenerator<StdRng> {
+    pub fn from_seed(seed: u64) -> ProblemGenerator<StdRng> {
+        ProblemGenerator::new(StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: Rng> ProblemGenerator<R> {
+    pub fn new(rng: R) -> ProblemGenerator<R> {
+        ProblemGenerator {
+            rng,
+            recent_facts: BTreeMap::new(),
+        }
+    }
+
+    pub fn generate(&mut self, group: &WaveGroup, difficulty: &Difficulty) -> Problem {
+        if let Some(problem) = self.generate_fact(group, None) {
+            return problem;
+        }
+        match group.operation {
+            Operation::Expression => return self.generate_expression(group, difficulty),
+            Operation::Fraction | Operation::Decimal => {
+                return self.generate_rational(group, difficulty)
+            }
+            operation if operation.is_number_sense() => {
+                return self.generate_number_sense(group, difficulty)
+            }
+            operation if INTEGER_OPERATIONS.contains(&operation) => {
+                return self.generate_integer(group, difficulty)
+            }
+            _ => (),
+        }
+        match group.missing {
+            Some(missing) => self.generate_missing(group, difficulty, missing),
+            None => self.generate_result(group, difficulty),
+        }
+    }
+
+    // asks for the result, like 6X7
+    fn generate_result(&mut self, group: &WaveGroup, difficulty: &Difficulty) -> Problem {
+        let (min_number, max_number) =
+            scale_range(group.min_number, group.max_number, difficulty);
+
+        let (mut num1, mut num2) = if group.operation == Operation::Divide {
+            // build the problem backwards from divisor and quotient so it
+            // always divides evenly and always terminates
+            let (min_divisor, max_divisor) = group.divisor_range();
+            let (min_divisor, max_divisor) = scale_range(min_divisor, max_divisor, difficulty);
+            let (min_quotient, max_quotient) = group.quotient_range();
+            let (min_quotient, max_quotient) = scale_range(min_quotient, max_quotient, difficulty);
+            let divisor = gen_nonzero(&mut self.rng, min_divisor, max_divisor);
+            let quotient = self.rng.gen_range(min_quotient, max_quotient);
+            (divisor * quotient, divisor)
+        } else {
+            (
+                self.rng.gen_range(min_number, max_number),
+                self.rng.gen_range(min_number, max_number),
+            )
+        };
+
+        if group.goes_negative(difficulty, group.operation, num1, num2) {
+            std::mem::swap(&mut num1, &mut num2);
+        }
+        let num1 = signed(&mut self.rng, group.first_sign, num1);
+        let num2 = signed(&mut self.rng, group.second_sign, num2);
+
+        // scale_range keeps operands within MAX_OPERAND, where every answer fits
+        let problem = Problem::new(group.operation, num1, num2)
+            .expect("operands within MAX_OPERAND always have an answer");
+        debug_assert!(problem.is_valid(), "invalid problem {:?}", problem);
+        problem
+    }
+
+    // Asks for an operand, trying new numbers until exactly one number
+    // fits the gap. Ranges with nothing but zeros fall back to asking
+    // for the result.
+    fn generate_missing(
+        &mut self,
+        group: &WaveGroup,
+        difficulty: &Difficulty,
+        missing: Missing,
+    ) -> Problem {
+        let mut problem = self.generate_result(group, difficulty);
+        for _ in 0..MISSING_TRIES {
+            if let Some(problem) = self.ask_missing(&problem, missing) {
+                debug_assert!(problem.is_valid(), "invalid problem {:?}", problem);
+                return problem;
+            }
+            problem = self.generate_result(group, difficulty);
+        }
+        problem
+    }
+
+    // the problem asking for an operand missing allows, None when no
+    // single number fits either gap
+    pub fn ask_missing(&mut self, problem: &Problem, missing: Missing) -> Option<Problem> {
+        let indices = match missing {
+            Missing::First => vec![0],
+            Missing::Second => vec![1],
+            Missing::Either if self.rng.gen() => vec![0, 1],
+            Missing::Either => vec![1, 0],
+        };
+        indices
+            .into_iter()
+            .find(|i| problem.operand_is_unique(*i))
+            .map(|index| problem.with_missing(index))
+    }
+}
+
+// new numbers tried before giving up on a missing operand
+const MISSING_TRIES: usize = 20;
+
+impl<R: Rng> ProblemGenerator<R> {
+    /// The rest of a boss's problems after `first`, each one starting
+    /// from the answer before it: 3X4, then 12+5, then 17-6. Number sense
+    /// and fact bosses just ask more questions.
+    pub fn generate_chain(
+        &mut self,
+        first: &Problem,
+        group: &WaveGroup,
+        difficulty: &Difficulty,
+        count: usize,
+    ) -> Vec<Problem> {
+        let (min_number, max_number) =
+            scale_range(group.min_number, group.max_number, difficulty);
+        let mut chain: Vec<Problem> = Vec::new();
+        for _ in 0..count {
+            let last = match chain.last().unwrap_or(first).answer.number() {
+                Some(last) if !first.operation.is_number_sense() && group.facts.is_none() => last,
+                _ => {
+                    chain.push(self.generate(group, difficulty));
+                    continue;
+                }
+            };
+            let num = self.rng.gen_range(min_number, max_number);
+            let mut operation = if self.rng.gen() {
+                Operation::Add
+            } else {
+                Operation::Subtract
+            };
+            if group.goes_negative(difficulty, operation, last, Rational::from(num)) {
+                operation = Operation::Add;
+            }
+            let next = if last.is_integer() {
+                Problem::new(operation, last.numer, num).ok()
+            } else {
+                // carries on in fractions or decimals
+                let expression =
+                    Expr::binary(Expr::Number(last), operation, Expr::Number(Rational::from(num)));
+                Some(Problem::from_expression(first.operation, expression))
+            };
+            // starts over when the answer gets too big to carry on from
+            chain.push(next.unwrap_or_else(|| self.generate(group, difficulty)));
+        }
+        chain
+    }
+}
+
+// random expressions tried before falling back to a plain sum
+const EXPRESSION_TRIES: usize = 50;
+
+impl<R: Rng> ProblemGenerator<R> {
+    fn generate_expression(&mut self, group: &WaveGroup, difficulty: &Difficulty) -> Problem {
+        let (min_number, max_number) =
+            scale_range(group.min_number, group.max_number, difficulty);
+        let operations = group.operations();
+        for _ in 0..EXPRESSION_TRIES {
+            let expression = Expr::generate(
+                &mut self.rng,
+                group.expression_depth(),
+                &operations,
+                min_number,
+                max_number,
+            );
+            let natural = group.allows_negative_answers(difficulty) || expression.is_natural();
+            match expression.eval() {
+                Some(answer)
+                    if natural
+                        && answer.is_integer()
+                        && answer
+                            .checked_abs()
+                            .is_some_and(|n| n <= Rational::from(group.max_answer())) =>
+                {
+                    return Problem::from_expression(Operation::Expression, expression);
+                }
+                _ => (),
+            }
+        }
+        let mut number =
+            || Expr::Number(Rational::from(self.rng.gen_range(min_number, max_number).max(0)));
+        let sum = Expr::binary(number(), Operation::Add, number());
+        Problem::from_expression(Operation::Expression, Expr::binary(sum, Operation::Add, number()))
+    }
+
+    // One operation on two fractions or decimals. Fractions are proper,
+    // with denominators from min_number..max_number. Decimals are whole
+    // numbers from min_number..max_number plus halves, quarters or tenths.
+    fn generate_rational(&mut self, group: &WaveGroup, difficulty: &Difficulty) -> Problem {
+        let (min_number, max_number) =
+            scale_range(group.min_number, group.max_number, difficulty);
+        let operations = group.operations();
+        let operation = operations[self.rng.gen_range(0, operations.len())];
+        let mut number = || match group.operation {
+            Operation::Fraction => {
+                // denominators start at 2, so these never fail
+                let denom = self.rng.gen_range(min_number.max(2), max_number.max(3));
+                let numer = self.rng.gen_range(1, denom);
+                Rational::new(numer, denom).unwrap_or_else(|_| Rational::zero())
+            }
+            _ => {
+                let denom = DECIMAL_DENOMINATORS[self.rng.gen_range(0, DECIMAL_DENOMINATORS.len())];
+                let numer = self.rng.gen_range(min_number * denom, max_number * denom);
+                Rational::new(numer, denom).unwrap_or_else(|_| Rational::zero())
+            }
+        };
+        let (mut a, mut b) = (number(), number());
+        if group.goes_negative(difficulty, operation, a, b) {
+            std::mem::swap(&mut a, &mut b);
+        }
+        let expression = Expr::binary(Expr::Number(a), operation, Expr::Number(b));
+        Problem::from_expression(group.operation, expression)
+    }
+}
+
+// decimal problems stick to numbers that end quickly
+const DECIMAL_DENOMINATORS: [i32; 3] = [2, 4, 10];
+
+// how many problems adaptive mode picks between
+const ADAPTIVE_CANDIDATES: usize = 6;
+
+impl<R: Rng> ProblemGenerator<R> {
+    /// Generates a few problems and picks one, favoring facts the
+    /// player misses or answers slowly
+    pub fn generate_adaptive(
+        &mut self,
+        group: &WaveGroup,
+        difficulty: &Difficulty,
+        mastery: &MasteryModel,
+    ) -> Problem {
+        if let Some(problem) = self.generate_fact(group, Some(mastery)) {
+            return problem;
+        }
+        let mut candidates: Vec<(Problem, f32)> = (0..ADAPTIVE_CANDIDATES)
+            .map(|_| {
+                let problem = self.generate(group, difficulty);
+                let weight = mastery.weight(&problem.display_text);
+                (problem, weight)
+            })
+            .collect();
+        let total: f32 = candidates.iter().map(|(_, weight)| weight).sum();
+        let mut pick = self.rng.gen_range(0.0, total);
+        for i in 0..candidates.len() {
+            if pick < candidates[i].1 {
+                return candidates.swap_remove(i).0;
+            }
+            pick -= candidates[i].1;
+        }
+        candidates.pop().unwrap().0
+    }
+}
+
+// Applies the difficulty multipliers to a min..max range, always leaving
+// at least one number in it and staying within MAX_OPERAND
+pub fn scale_range(min: i32, max: i32, difficulty: &Difficulty) -> (i32, i32) {
+    let min = ((min as f32 * difficulty.min_number) as i32).clamp(-MAX_OPERAND, MAX_OPERAND);
+    let max = ((max as f32 * difficulty.max_number) as i32).min(MAX_OPERAND + 1);
+    (min, max.max(min + 1))
+}
+
+// Gives n the sign asked for, None leaves it as it was drawn
+fn signed<R: Rng>(rng: &mut R, sign: Option<Sign>, n: i32) -> i32 {
+    match sign {
+        None => n,
+        Some(Sign::Positive) => n.abs(),
+        Some(Sign::Negative) => -n.abs(),
+        Some(Sign::Either) if rng.gen() => -n.abs(),
+        Some(Sign::Either) => n.abs(),
+    }
+}
+
+// Picks a number from min..max skipping zero, falls back to 1 if zero
+// is the only choice. WaveGroup::validate rejects ranges like that.
+pub fn gen_nonzero<R: Rng>(rng: &mut R, min: i32, max: i32) -> i32 {
+    let has_zero = min <= 0 && 0 < max;
+    let count = max - min - if has_zero { 1 } else { 0 };
+    if count <= 0 {
+        return 1;
+    }
+    let n = min + rng.gen_range(0, count);
+    if has_zero && n >= 0 {
+        n + 1
+    } else {
+        n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPERATIONS: [Operation; 15] = [
+        Operation::Add,
+        Operation::Subtract,
+        Operation::Multiply,
+        Operation::Divide,
+        Operation::Expression,
+        Operation::Fraction,
+        Operation::Decimal,
+        Operation::Compare,
+        Operation::Round,
+        Operation::Prime,
+        Operation::PlaceValue,
+        Operation::Power,
+        Operation::SquareRoot,
+        Operation::Modulo,
+        Operation::DivideWithRemainder,
+    ];
+    const RANGES: [(i32, i32); 4] = [(0, 6), (1, 13), (-12, 12), (10, 100)];
+    const PROBLEMS: usize = 50;
+
+    // every operation over every range, with a missing number where it's allowed
+    fn groups() -> Vec<WaveGroup> {
+        let mut groups = Vec::new();
+        for operation in OPERATIONS.iter() {
+            for (min_number, max_number) in RANGES.iter() {
+                let group = WaveGroup {
+                    operation: *operation,
+                    speed: 1.0,
+                    num_ships: 1,
+                    min_number: *min_number,
+                    max_number: *max_number,
+                    ..Default::default()
+                };
+                if operation.is_basic() {
+                    groups.push(WaveGroup {
+                        missing: Some(Missing::Either),
+                        ..group.clone()
+                    });
+                }
+                groups.push(group);
+            }
+        }
+        groups
+    }
+
+    fn difficulties() -> Vec<Difficulty> {
+        let mut difficulties = Difficulty::new();
+        difficulties.push(Difficulty::default());
+        difficulties
+    }
+
+    #[test]
+    fn generated_problems_are_valid() {
+        let mut generator = ProblemGenerator::from_seed(1);
+        for group in groups() {
+            assert!(group.validate().is_empty(), "{:?}", group.validate());
+            for difficulty in difficulties() {
+                for _ in 0..PROBLEMS {
+                    let problem = generator.generate(&group, &difficulty);
+                    assert!(problem.is_valid(), "invalid problem {:?}", problem);
+                    assert!(problem.is_answer(&problem.answer_text()), "{:?}", problem);
+                    assert_eq!(
+                        problem.answer_text().parse::<Answer>(),
+                        Ok(problem.answer),
+                        "{:?}",
+                        problem
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_problems() {
+        let texts = |seed: u64| {
+            let mut generator = ProblemGenerator::from_seed(seed);
+            let mut texts = Vec::new();
+            for group in groups() {
+                for difficulty in difficulties() {
+                    texts.push(generator.generate(&group, &difficulty).display_text);
+                }
+            }
+            texts
+        };
+        assert_eq!(texts(7), texts(7));
+        assert_ne!(texts(7), texts(8));
+    }
+
+    #[test]
+    fn big_ranges_fit() {
+        let mut generator = ProblemGenerator::from_seed(3);
+        let hardest = Difficulty::new().pop().unwrap();
+        for operation in OPERATIONS[..4].iter() {
+            let group = WaveGroup {
+                operation: *operation,
+                speed: 1.0,
+                num_ships: 1,
+                min_number: -MAX_OPERAND,
+                max_number: MAX_OPERAND,
+                ..Default::default()
+            };
+            assert!(group.validate().is_empty(), "{:?}", group.validate());
+            for _ in 0..PROBLEMS {
+                assert!(generator.generate(&group, &hardest).is_valid());
+            }
+            let too_big = WaveGroup {
+                max_number: MAX_OPERAND + 1,
+                ..group
+            };
+            assert_eq!(too_big.validate().len(), 1);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::data_file::*;
+use crate::level::*;
+use crate::mastery::*;
+use crate::practice::*;
+use crate::progress::*;
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MAX_NAME_LENGTH: usize = 16;
+pub const PROFILE_FILE: &str = "profile.json";
+
+/// Where profiles are saved, one directory per player:
+/// %APPDATA%\MathDefense on windows, ~/Library/Application Support/MathDefense
+/// on mac, and $XDG_DATA_HOME/mathdefense or ~/.local/share/mathdefense elsewhere.
+pub fn data_dir() -> PathBuf {
+    let home = || env::var("HOME").map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("."));
+    if cfg!(target_os = "windows") {
+        env::var("APPDATA")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| home())
+            .join("MathDefense")
+    } else if cfg!(target_os = "macos") {
+        home().join("Library/Application Support/MathDefense")
+    } else {
+        env::var("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| home().join(".local/share"))
+            .join("mathdefense")
+    }
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct Stats {
+    pub levels_started: u32,
+    pub levels_completed: u32,
+    pub aliens_destroyed: u32,
+    pub wrong_answers: u32,
+    pub lives_lost: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Profile {
+    pub name: String,
+    // the last difficulty played, by name
+    pub difficulty: String,
+    #[serde(default)]
+    pub progress: Progress,
+    #[serde(default)]
+    pub stats: Stats,
+    // adaptive mode picks problems and speed from mastery
+    #[serde(default)]
+    pub adaptive: bool,
+    #[serde(default)]
+    pub mastery: MasteryModel,
+    #[serde(default)]
+    pub achievements: Vec<String>,
+    #[serde(default)]
+    pub practice: PracticeSettings,
+}
+
+impl Profile {
+    pub fn new(name: String) -> Profile {
+        Profile {
+            name,
+            difficulty: String::new(),
+            progress: Progress::default(),
+            stats: Stats::default(),
+            adaptive: false,
+            mastery: MasteryModel::default(),
+            achievements: Vec::new(),
+            practice: PracticeSettings::default(),
+        }
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        self.dir_in(&data_dir())
+    }
+
+    fn dir_in(&self, data_dir: &Path) -> PathBuf {
+        data_dir.join("profiles").join(slugify(&self.name))
+    }
+
+    /// Names become directory names, so a new one can't match another
+    /// profile's once slugified, like "Sam" and "sam"
+    pub fn is_new_name(name: &str, profiles: &[Profile]) -> bool {
+        let slug = slugify(name);
+        !slug.is_empty() && !profiles.iter().any(|profile| slugify(&profile.name) == slug)
+    }
+
+    /// Loads every profile in the data directory, sorted by name
+    pub fn load_all() -> Vec<Profile> {
+        Profile::load_all_in(&data_dir())
+    }
+
+    pub fn load_all_in(data_dir: &Path) -> Vec<Profile> {
+        let mut profiles = Vec::new();
+        if let Ok(entries) = fs::read_dir(data_dir.join("profiles")) {
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let path = entry.path().join(PROFILE_FILE);
+                match read_validated(&path, |_: &Profile| Vec::new()) {
+                    Ok(profile) => profiles.push(profile),
+                    Err(errors) => {
+                        println!("Error loading profile {}", path.display());
+                        for error in &errors {
+                            println!("  {}", error);
+                        }
+                    }
+                }
+            }
+        }
+        profiles.sort_by_key(|profile| profile.name.to_lowercase());
+        profiles
+    }
+
+    pub fn save(&self) {
+        self.save_in(&data_dir())
+    }
+
+    pub fn save_in(&self, data_dir: &Path) {
+        save(&self.dir_in(data_dir).join(PROFILE_FILE), "profile", self, |_| Vec::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process;
+
+    #[test]
+    fn names_are_unique_once_slugified() {
+        let profiles = vec![Profile::new("Sam Smith".to_string())];
+        assert!(Profile::is_new_name("Sam", &profiles));
+        assert!(!Profile::is_new_name("sam smith", &profiles));
+        assert!(!Profile::is_new_name("Sam  Smith!", &profiles));
+        assert!(!Profile::is_new_name("", &profiles));
+        assert!(!Profile::is_new_name("!?", &profiles));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = env::temp_dir().join(format!("mathdefense-profiles-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut zoe = Profile::new("zoe".to_string());
+        zoe.difficulty = "Cadet".to_string();
+        zoe.adaptive = true;
+        zoe.stats.aliens_destroyed = 12;
+        zoe.achievements.push("first-level".to_string());
+        zoe.save_in(&dir);
+        Profile::new("Alex".to_string()).save_in(&dir);
+
+        let profiles = Profile::load_all_in(&dir);
+        let _ = fs::remove_dir_all(&dir);
+        let names: Vec<&str> = profiles.iter().map(|profile| profile.name.as_str()).collect();
+        assert_eq!(names, vec!["Alex", "zoe"]);
+        let loaded = &profiles[1];
+        assert_eq!(loaded.difficulty, "Cadet");
+        assert!(loaded.adaptive);
+        assert_eq!(loaded.stats.aliens_destroyed, 12);
+        assert_eq!(loaded.achievements, vec!["first-level"]);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::level::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Deserialize, Serialize, Default, Clone)]
+pub struct DifficultyProgress {
+    pub unlocked: bool,
+    #[serde(default)]
+    pub best_score: Option<u32>,
+    // seconds since the unix epoch, one per time the level was beaten
+    #[serde(default)]
+    pub completed: Vec<u64>,
+}
+
+#[derive(Deserialize, Serialize, Default)]
+pub struct LevelProgress {
+    // keyed by difficulty name, so tiers can be added or reordered
+    pub difficulties: BTreeMap<String, DifficultyProgress>,
+}
+
+/// What a player has done, saved with their Profile rather than in the
+/// level definitions so new content doesn't wipe progress. Levels are
+/// keyed by Level::id.
+#[derive(Deserialize, Serialize, Default)]
+pub struct Progress {
+    pub levels: BTreeMap<String, LevelProgress>,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Progress {
+    fn difficulty_mut(&mut self, level_id: &str, difficulty: &str) -> &mut DifficultyProgress {
+        self.levels
+            .entry(level_id.to_string())
+            .or_default()
+            .difficulties
+            .entry(difficulty.to_string())
+            .or_default()
+    }
+
+    pub fn get(&self, level_id: &str, difficulty: &str) -> Option<&DifficultyProgress> {
+        self.levels
+            .get(level_id)
+            .and_then(|level| level.difficulties.get(difficulty))
+    }
+
+    /// A level is playable if the level file starts it unlocked or the
+    /// player has unlocked it
+    pub fn is_unlocked(&self, level: &Level, difficulty: &str) -> bool {
+        level.starts_unlocked(difficulty)
+            || self
+                .get(&level.id(), difficulty)
+                .is_some_and(|progress| progress.unlocked)
+    }
+
+    pub fn unlock(&mut self, level_id: &str, difficulty: &str) {
+        self.difficulty_mut(level_id, difficulty).unlocked = true;
+    }
+
+    pub fn record_completion(&mut self, level_id: &str, difficulty: &str, score: Option<u32>) {
+        let progress = self.difficulty_mut(level_id, difficulty);
+        progress.unlocked = true;
+        progress.completed.push(now());
+        if let Some(score) = score {
+            if progress.best_score.is_none_or(|best| score > best) {
+                progress.best_score = Some(score);
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+// decimals are only written out to this many places
+const MAX_DECIMAL_PLACES: u32 = 6;
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// An exact fraction, always reduced with a positive denominator, so
+/// 6/8 and 0.75 are both equal to 3/4. Fraction problems also check the
+/// answer was typed in lowest terms, see in_lowest_terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    pub numer: i32,
+    pub denom: i32,
+}
+
+impl Rational {
+    pub fn new(numer: i32, denom: i32) -> Result<Rational, String> {
+        Rational::reduce(numer as i64, denom as i64)
+            .ok_or_else(|| format!("{}/{} has a zero denominator", numer, denom))
+    }
+
+    pub fn zero() -> Rational {
+        Rational::from(0)
+    }
+
+    // None for a zero denominator or a result that doesn't fit
+    fn reduce(numer: i64, denom: i64) -> Option<Rational> {
+        if denom == 0 {
+            return None;
+        }
+        let sign = if denom < 0 { -1 } else { 1 };
+        let divisor = gcd(numer, denom).max(1);
+        let numer = sign * numer / divisor;
+        let denom = sign * denom / divisor;
+        let fits = |n: i64| i32::MIN as i64 <= n && n <= i32::MAX as i64;
+        if !fits(numer) || !fits(denom) {
+            return None;
+        }
+        Some(Rational {
+            numer: numer as i32,
+            denom: denom as i32,
+        })
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom == 1
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.numer < 0
+    }
+
+    /// None for i32::MIN, which has no positive i32
+    pub fn checked_abs(self) -> Option<Rational> {
+        Some(Rational {
+            numer: self.numer.checked_abs()?,
+            denom: self.denom,
+        })
+    }
+
+    pub fn checked_add(self, other: Rational) -> Option<Rational> {
+        Rational::reduce(
+            self.numer as i64 * other.denom as i64 + other.numer as i64 * self.denom as i64,
+            self.denom as i64 * other.denom as i64,
+        )
+    }
+
+    pub fn checked_sub(self, other: Rational) -> Option<Rational> {
+        self.checked_add(Rational {
+            numer: other.numer.checked_neg()?,
+            denom: other.denom,
+        })
+    }
+
+    pub fn checked_mul(self, other: Rational) -> Option<Rational> {
+        Rational::reduce(
+            self.numer as i64 * other.numer as i64,
+            self.denom as i64 * other.denom as i64,
+        )
+    }
+
+    pub fn checked_div(self, other: Rational) -> Option<Rational> {
+        Rational::reduce(
+            self.numer as i64 * other.denom as i64,
+            self.denom as i64 * other.numer as i64,
+        )
+    }
+
+    /// Written as a decimal like 1.25, None when it never ends, like 1/3
+    pub fn to_decimal(self) -> Option<String> {
+        let mut places = 0;
+        let mut scale: i64 = 1;
+        while scale % self.denom as i64 != 0 {
+            if places == MAX_DECIMAL_PLACES {
+                return None;
+            }
+            places += 1;
+            scale *= 10;
+        }
+        let scaled = (self.numer as i64 * (scale / self.denom as i64)).abs();
+        let sign = if self.is_negative() { "-" } else { "" };
+        if places == 0 {
+            return Some(format!("{}{}", sign, scaled));
+        }
+        Some(format!(
+            "{}{}.{:0width$}",
+            sign,
+            scaled / scale,
+            scaled % scale,
+            width = places as usize
+        ))
+    }
+}
+
+impl From<i32> for Rational {
+    fn from(n: i32) -> Rational {
+        Rational { numer: n, denom: 1 }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        (self.numer as i64 * other.denom as i64).cmp(&(other.numer as i64 * self.denom as i64))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+/// Reads what the player typed: 3, -3, 3/4 or 1.25
+impl FromStr for Rational {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Rational, String> {
+        let invalid = || format!("not a number {}", s);
+        let s = s.trim();
+        if let Some(slash) = s.find('/') {
+            let numer = s[..slash].parse::<i32>().map_err(|_| invalid())?;
+            let denom = s[slash + 1..].parse::<i32>().map_err(|_| invalid())?;
+            return Rational::new(numer, denom).map_err(|_| invalid());
+        }
+        if let Some(dot) = s.find('.') {
+            let (whole, fraction) = (&s[..dot], &s[dot + 1..]);
+            let negative = whole.starts_with('-');
+            let whole = whole.strip_prefix('-').unwrap_or(whole);
+            if !whole.chars().all(|c| c.is_ascii_digit())
+                || fraction.is_empty()
+                || fraction.len() > MAX_DECIMAL_PLACES as usize
+                || !fraction.chars().all(|c| c.is_ascii_digit())
+            {
+                return Err(invalid());
+            }
+            let whole = if whole.is_empty() {
+                0
+            } else {
+                whole.parse::<i64>().map_err(|_| invalid())?
+            };
+            let scale = 10i64.pow(fraction.len() as u32);
+            let numer = whole
+                .checked_mul(scale)
+                .and_then(|n| n.checked_add(fraction.parse::<i64>().ok()?))
+                .ok_or_else(invalid)?;
+            let numer = if negative { -numer } else { numer };
+            return Rational::reduce(numer, scale).ok_or_else(invalid);
+        }
+        s.parse::<i32>().map(Rational::from).map_err(|_| invalid())
+    }
+}
+
+/// Whether s is a fraction or whole number that can't be reduced, like
+/// 3/4 or 2, and not 6/8, 4/2 or 0.75
+pub fn in_lowest_terms(s: &str) -> bool {
+    let s = s.trim();
+    if s.contains('.') {
+        return false;
+    }
+    match s.parse::<Rational>() {
+        Ok(n) if n.is_integer() => s.parse::<i32>().is_ok(),
+        Ok(n) => s == n.to_string(),
+        Err(_) => false,
+    }
+}
+
+// whole numbers are saved as plain numbers so older answer logs still load
+impl Serialize for Rational {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.is_integer() {
+            serializer.serialize_i32(self.numer)
+        } else {
+            serializer.serialize_str(&self.to_string())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Rational {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Rational, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Saved {
+            Number(i32),
+            Text(String),
+        }
+        match Saved::deserialize(deserializer)? {
+            Saved::Number(n) => Ok(Rational::from(n)),
+            Saved::Text(text) => text.parse::<Rational>().map_err(de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_equivalent_values() {
+        let three_quarters = Rational::new(3, 4).unwrap();
+        for text in ["3/4", "6/8", "0.75", "-3/-4"].iter() {
+            assert_eq!(text.parse::<Rational>(), Ok(three_quarters));
+        }
+        assert!("3/0".parse::<Rational>().is_err());
+        assert!(Rational::new(3, 0).is_err());
+    }
+
+    #[test]
+    fn parses_player_input() {
+        let parse = |text: &str| text.parse::<Rational>();
+        assert_eq!(parse("-3"), Ok(Rational::from(-3)));
+        assert_eq!(parse("-1.25"), Ok(Rational::new(-5, 4).unwrap()));
+        assert_eq!(parse(".5"), Ok(Rational::new(1, 2).unwrap()));
+        // parses fine, the lowest terms check is separate
+        assert_eq!(parse("3/6"), Ok(Rational::new(1, 2).unwrap()));
+        assert!(!in_lowest_terms("3/6"));
+        for text in [
+            "1/0",
+            "99999999999999999999.5",
+            "9999999999999999.5",
+            "99999999999.5",
+            "99999999999",
+            "1/99999999999",
+            "",
+            "-",
+            "5.",
+            "--5.5",
+            "+-5.5",
+        ]
+        .iter()
+        {
+            assert!(parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn abs_of_min_is_none() {
+        assert_eq!(Rational::from(-3).checked_abs(), Some(Rational::from(3)));
+        assert_eq!(Rational::from(i32::MIN).checked_abs(), None);
+    }
+
+    #[test]
+    fn lowest_terms() {
+        for text in ["3/4", "-3/4", "2", "-2"].iter() {
+            assert!(in_lowest_terms(text), "{}", text);
+        }
+        for text in ["6/8", "4/2", "3/-4", "0.75", "2.0", "3/0", "x"].iter() {
+            assert!(!in_lowest_terms(text), "{}", text);
+        }
+    }
+}
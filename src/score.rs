@@ -0,0 +1,201 @@
+use crate::data_file::*;
+use crate::level::*;
+use crate::mastery::*;
+use crate::profile::*;
+use crate::progress::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const HIGH_SCORE_FILE: &str = "highscores.json";
+// entries kept per level and difficulty
+pub const MAX_HIGH_SCORES: usize = 5;
+// right answers in a row needed to bump the multiplier
+pub const COMBO_STEP: u32 = 5;
+pub const MAX_COMBO: u32 = 4;
+
+// harder operations are worth more
+pub fn operation_points(operation: Operation) -> f32 {
+    match operation {
+        Operation::Add => 10.0,
+        Operation::Subtract => 15.0,
+        Operation::Multiply => 20.0,
+        Operation::Divide => 25.0,
+        Operation::Expression => 30.0,
+        Operation::Fraction => 30.0,
+        Operation::Decimal => 25.0,
+        Operation::Compare | Operation::Round | Operation::Prime | Operation::PlaceValue => 15.0,
+        Operation::Power => 25.0,
+        Operation::SquareRoot => 20.0,
+        Operation::Modulo => 25.0,
+        Operation::DivideWithRemainder => 30.0,
+    }
+}
+
+/// Points and the current streak for one play of a level
+#[derive(Debug, Default, Clone)]
+pub struct Score {
+    pub points: u32,
+    pub streak: u32,
+}
+
+impl Score {
+    pub fn multiplier(&self) -> u32 {
+        (1 + self.streak / COMBO_STEP).min(MAX_COMBO)
+    }
+
+    /// Scores a destroyed alien, y is its height as a percent of the screen
+    /// and time_ms how long it took to answer. Returns the points earned.
+    pub fn hit(&mut self, operation: Operation, y: f32, time_ms: f32) -> u32 {
+        self.streak += 1;
+        // up to double for shooting high and for answering fast
+        let height = 1.0 + (0.9 - y).clamp(0.0, 1.0);
+        let speed = 1.0 + ((TARGET_ANSWER_MS - time_ms) / TARGET_ANSWER_MS).max(0.0);
+        let points =
+            (operation_points(operation) * height * speed) as u32 * self.multiplier();
+        self.points += points;
+        points
+    }
+
+    pub fn miss(&mut self) {
+        self.streak = 0;
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct HighScore {
+    pub name: String,
+    pub points: u32,
+    // seconds since the unix epoch
+    pub timestamp: u64,
+    // how long an endless run lasted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waves: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seconds: Option<u64>,
+}
+
+impl HighScore {
+    pub fn new(name: &str, points: u32) -> HighScore {
+        HighScore {
+            name: name.to_string(),
+            points,
+            timestamp: now(),
+            waves: None,
+            seconds: None,
+        }
+    }
+}
+
+/// The best scores on this computer across every profile, one table per
+/// level and difficulty, saved to highscores.json in the data directory.
+#[derive(Deserialize, Serialize, Default)]
+pub struct HighScores {
+    pub tables: BTreeMap<String, Vec<HighScore>>,
+}
+
+impl HighScores {
+    // tables are kept by difficulty name, like "addition-attack/Rookie"
+    fn key(level_id: &str, difficulty: &str) -> String {
+        format!("{}/{}", level_id, difficulty)
+    }
+
+    pub fn table(&self, level_id: &str, difficulty: &str) -> &[HighScore] {
+        match self.tables.get(&HighScores::key(level_id, difficulty)) {
+            Some(table) => table,
+            None => &[],
+        }
+    }
+
+    /// Adds a score if it makes the table, returns its place from 0
+    pub fn add(&mut self, level_id: &str, difficulty: &str, entry: HighScore) -> Option<usize> {
+        let table = self
+            .tables
+            .entry(HighScores::key(level_id, difficulty))
+            .or_default();
+        let place = table
+            .iter()
+            .position(|score| entry.points > score.points)
+            .unwrap_or(table.len());
+        if place >= MAX_HIGH_SCORES {
+            return None;
+        }
+        table.insert(place, entry);
+        table.truncate(MAX_HIGH_SCORES);
+        Some(place)
+    }
+
+    pub fn load() -> HighScores {
+        let path = data_dir().join(HIGH_SCORE_FILE);
+        match read_validated(&path, |_: &HighScores| Vec::new()) {
+            Ok(scores) => scores,
+            Err(errors) => {
+                println!("No high scores loaded");
+                for error in &errors {
+                    println!("  {}", error);
+                }
+                HighScores::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        save(&data_dir().join(HIGH_SCORE_FILE), "high score", self, |_| Vec::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_weigh_operation_height_and_speed() {
+        let points = |operation, y, time_ms| Score::default().hit(operation, y, time_ms);
+        // at the bottom and at the target time there is no bonus
+        assert_eq!(points(Operation::Add, 0.9, TARGET_ANSWER_MS), 10);
+        assert_eq!(points(Operation::Divide, 0.9, TARGET_ANSWER_MS), 25);
+        assert_eq!(points(Operation::Add, 1.0, TARGET_ANSWER_MS * 2.0), 10);
+        // up to double for each
+        assert_eq!(points(Operation::Add, 0.4, TARGET_ANSWER_MS), 15);
+        assert_eq!(points(Operation::Add, 0.9, 0.0), 20);
+        assert_eq!(points(Operation::Add, -0.5, 0.0), 40);
+    }
+
+    #[test]
+    fn streaks_raise_the_multiplier() {
+        let mut score = Score::default();
+        for _ in 1..COMBO_STEP {
+            assert_eq!(score.hit(Operation::Add, 0.9, TARGET_ANSWER_MS), 10);
+        }
+        assert_eq!(score.hit(Operation::Add, 0.9, TARGET_ANSWER_MS), 20);
+        for _ in 0..COMBO_STEP * MAX_COMBO * 2 {
+            score.hit(Operation::Add, 0.9, TARGET_ANSWER_MS);
+        }
+        assert_eq!(score.multiplier(), MAX_COMBO);
+        score.miss();
+        assert_eq!(score.multiplier(), 1);
+        assert_eq!(score.hit(Operation::Add, 0.9, TARGET_ANSWER_MS), 10);
+    }
+
+    #[test]
+    fn high_scores_are_ordered_and_truncated() {
+        let mut scores = HighScores::default();
+        for points in [10, 30, 20, 50, 40].iter() {
+            assert!(scores.add("level", "Rookie", HighScore::new("a", *points)).is_some());
+        }
+        let points = |scores: &HighScores| -> Vec<u32> {
+            scores.table("level", "Rookie").iter().map(|score| score.points).collect()
+        };
+        assert_eq!(points(&scores), vec![50, 40, 30, 20, 10]);
+
+        assert_eq!(scores.add("level", "Rookie", HighScore::new("b", 5)), None);
+        // ties go after the score already there
+        assert_eq!(scores.add("level", "Rookie", HighScore::new("b", 10)), None);
+        assert_eq!(scores.add("level", "Rookie", HighScore::new("b", 35)), Some(2));
+        assert_eq!(points(&scores), vec![50, 40, 35, 30, 20]);
+        assert_eq!(scores.table("level", "Rookie")[2].name, "b");
+
+        assert!(scores.table("level", "Cadet").is_empty());
+        assert_eq!(scores.add("level", "Cadet", HighScore::new("c", 1)), Some(0));
+    }
+}
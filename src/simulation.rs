@@ -0,0 +1,854 @@
+use ggez::event::KeyCode;
+use ggez::nalgebra as na;
+use rand::*;
+
+use crate::alien::*;
+use crate::analytics::*;
+use crate::answer::*;
+use crate::difficulty::*;
+use crate::endless::*;
+use crate::events::*;
+use crate::explosion::*;
+use crate::ggez_utility::*;
+use crate::level::*;
+use crate::mastery::*;
+use crate::practice::*;
+use crate::problem::*;
+use crate::progress::*;
+use crate::score::*;
+use crate::turret::*;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GameState {
+    ProfileSelect,
+    DifficultySelect,
+    ModeSelect,
+    PackSelect,
+    LevelSelect,
+    PracticeSetup,
+    PracticeSummary,
+    LevelComplete,
+    LevelTransition(f32),
+    Playing,
+    Dying,
+    Dead,
+    Won,
+}
+
+impl GameState {
+    // menus are run by the game, everything else by the Simulation
+    pub fn is_menu(&self) -> bool {
+        matches!(
+            self,
+            GameState::ProfileSelect
+                | GameState::DifficultySelect
+                | GameState::ModeSelect
+                | GameState::PackSelect
+                | GameState::LevelSelect
+                | GameState::PracticeSetup
+                | GameState::PracticeSummary
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GameMode {
+    Campaign,
+    Practice,
+    Endless,
+    MadMinute,
+}
+
+pub fn get_lowest_living_alien(aliens: &[Alien]) -> Option<usize> {
+    aliens
+        .iter()
+        .enumerate()
+        .filter(|(_, alien)| alien.state != AlienState::Dead)
+        .max_by_key(|(_, alien)| (alien.pos[1] * 1000.0) as i32)
+        .map(|(index, _)| index)
+}
+
+pub fn gen_alien(
+    group: &WaveGroup,
+    difficulty: &Difficulty,
+    generator: &mut ProblemGenerator,
+    mastery: Option<&MasteryModel>,
+    pos: na::Point2<f32>,
+) -> Alien {
+    let problem = match mastery {
+        Some(mastery) => generator.generate_adaptive(group, difficulty, mastery),
+        None => generator.generate(group, difficulty),
+    };
+    Alien {
+        operation: group.operation,
+        speed: group.speed
+            * match mastery {
+                Some(mastery) => mastery.speed,
+                None => difficulty.speed,
+            },
+        pos,
+        problem,
+        attempts: Vec::new(),
+        time_ms: 0.0,
+        explosion: Explosion::new(0.0, na::Point2::new(0.0, 0.0)),
+        state: AlienState::Alive,
+        revealed: false,
+        chain: Vec::new(),
+        max_health: 1,
+        hit_ms: 0.0,
+    }
+}
+
+pub fn gen_aliens(
+    wave: &Wave,
+    difficulty: &Difficulty,
+    generator: &mut ProblemGenerator,
+    mastery: Option<&MasteryModel>,
+) -> Vec<Alien> {
+    let mut aliens: Vec<Alien> = Vec::new();
+    for group in &wave.groups {
+        let num_ships = (group.num_ships as f32 * difficulty.num_ships) as i32;
+        for i in 0..num_ships {
+            // generate an x coordinate for aliens, make
+            // sure it isn't too close to aliens at nearby
+            // y so they don't overlap
+            let mut x: f32 = generator.rng.gen_range(0.05, 0.95);
+            while aliens
+                .iter()
+                .rev()
+                .take(3)
+                .any(|alien| (alien.pos[0] - x).abs() < 0.1)
+            {
+                x = generator.rng.gen_range(0.05, 0.95);
+            }
+
+            let pos = na::Point2::new(x, -i as f32 * 0.3);
+            let mut alien = gen_alien(group, difficulty, generator, mastery, pos);
+            if let Some(steps) = group.boss_steps {
+                let problem = alien.problem.clone();
+                alien.chain = generator.generate_chain(&problem, group, difficulty, steps - 1);
+                alien.max_health = steps;
+            }
+            aliens.push(alien);
+        }
+    }
+    aliens.sort_by(|a, b| a.pos[0].partial_cmp(&b.pos[0]).unwrap());
+    aliens
+}
+
+/// All of the gameplay: aliens, turret, lives, waves and the state
+/// transitions between them. Drive it with key_up, text_input and
+/// update, then read what happened from events. It never touches
+/// audio, graphics or files itself, so it can run without a window.
+pub struct Simulation {
+    pub state: GameState,
+    pub levels: Vec<Level>,
+    pub difficulties: Vec<Difficulty>,
+    pub current_level: usize,
+    pub current_wave: usize,
+    pub difficulty: usize,
+    pub aliens: Vec<Alien>,
+    pub turret: Turret,
+    pub target: Option<usize>,
+    pub lives: usize,
+    pub score: Score,
+    pub generator: ProblemGenerator,
+    // the player's mastery, updated as they answer
+    pub mastery: MasteryModel,
+    pub adaptive: bool,
+    pub session: u64,
+    // the problems' seed, logged with every answer so a session can be replayed
+    pub seed: u64,
+    pub mode: GameMode,
+    // built for practice, endless and mad minutes, None in the campaign
+    pub custom_level: Option<Level>,
+    pub practice_summary: PracticeSummary,
+    // millis spent playing the current level or run
+    pub elapsed_ms: u64,
+    pub events: Vec<GameEvent>,
+    up_key: Option<KeyCode>,
+}
+
+impl Simulation {
+    pub fn new(levels: Vec<Level>, difficulties: Vec<Difficulty>, seed: u64) -> Simulation {
+        let mut generator = ProblemGenerator::from_seed(seed);
+        Simulation {
+            state: GameState::ProfileSelect,
+            levels,
+            difficulties,
+            current_level: 0,
+            current_wave: 0,
+            difficulty: 0,
+            aliens: Vec::new(),
+            turret: Turret::new(&mut generator.rng),
+            target: None,
+            lives: 2,
+            score: Score::default(),
+            generator,
+            mastery: MasteryModel::default(),
+            adaptive: false,
+            session: now(),
+            seed,
+            mode: GameMode::Campaign,
+            custom_level: None,
+            practice_summary: PracticeSummary::default(),
+            elapsed_ms: 0,
+            events: Vec::new(),
+            up_key: None,
+        }
+    }
+
+    pub fn key_up(&mut self, keycode: KeyCode) {
+        self.up_key = Some(keycode);
+    }
+
+    pub fn text_input(&mut self, ch: char) {
+        if self.state == GameState::Playing && self.turret.lockout <= 0.0 {
+            // y and n answer yes or no questions, r goes between a quotient and remainder
+            let ch = ch.to_ascii_lowercase();
+            if ch.is_ascii_digit() || "-/.ynr".contains(ch) {
+                self.turret.raw_text += &ch.to_string();
+            }
+        }
+    }
+
+    fn start(&mut self, mode: GameMode, custom_level: Option<Level>, level: usize, lives: usize) {
+        self.mode = mode;
+        self.custom_level = custom_level;
+        self.load_level_wave(level, 0);
+        self.lives = lives;
+        self.score = Score::default();
+        self.elapsed_ms = 0;
+        self.turret = Turret::new(&mut self.generator.rng);
+        self.state = GameState::Playing;
+    }
+
+    pub fn start_level(&mut self, level: usize) {
+        let lives = self.difficulty_tier().lives;
+        self.start(GameMode::Campaign, None, level, lives);
+        self.events.push(GameEvent::LevelStarted { level });
+    }
+
+    /// Practice has no lives, aliens stop above the turret and it ends
+    /// with a summary instead of Dead or Won
+    pub fn start_practice(&mut self, settings: &PracticeSettings) {
+        self.practice_summary = PracticeSummary::default();
+        self.start(GameMode::Practice, Some(settings.level()), 0, 0);
+        self.events.push(GameEvent::PracticeStarted);
+    }
+
+    /// Ends practice or a mad minute, showing the summary
+    pub fn finish_practice(&mut self) {
+        self.custom_level = None;
+        self.state = GameState::PracticeSummary;
+        let summary = self.practice_summary.clone();
+        self.events.push(if self.mode == GameMode::MadMinute {
+            GameEvent::MadMinuteFinished(summary)
+        } else {
+            GameEvent::PracticeFinished(summary)
+        });
+    }
+
+    /// As many aliens as possible in MAD_MINUTE_MS, a new one coming in
+    /// for each that dies. Like practice there are no lives.
+    pub fn start_mad_minute(&mut self, settings: &PracticeSettings) {
+        self.practice_summary = PracticeSummary::default();
+        self.start(GameMode::MadMinute, Some(settings.mad_minute_level()), 0, 0);
+        self.events.push(GameEvent::MadMinuteStarted);
+    }
+
+    pub fn time_left_ms(&self) -> u64 {
+        MAD_MINUTE_MS.saturating_sub(self.elapsed_ms)
+    }
+
+    /// Procedural waves that get harder until the last life is lost
+    pub fn start_endless(&mut self) {
+        let level = endless_level(&mut self.generator.rng);
+        let lives = self.difficulty_tier().lives;
+        self.start(GameMode::Endless, Some(level), 0, lives);
+        self.events.push(GameEvent::EndlessStarted);
+    }
+
+    /// Ends an endless run, when the player dies or quits, so it still
+    /// makes the leaderboard
+    pub fn finish_endless(&mut self) {
+        self.state = GameState::Dead;
+        self.events.push(GameEvent::EndlessFinished {
+            waves: self.current_wave,
+            time_ms: self.elapsed_ms,
+            score: self.score.points,
+        });
+    }
+
+    /// The difficulty being played. Practice and mad minutes stick to the
+    /// numbers the player chose, so they play levels as written.
+    pub fn difficulty_tier(&self) -> Difficulty {
+        match self.mode {
+            GameMode::Practice | GameMode::MadMinute => Difficulty::default(),
+            _ => self.difficulties.get(self.difficulty).cloned().unwrap_or_default(),
+        }
+    }
+
+    // the level being played, from the campaign or built for the mode
+    pub fn level(&self) -> &Level {
+        match &self.custom_level {
+            Some(level) => level,
+            None => &self.levels[self.current_level],
+        }
+    }
+
+    pub fn update(&mut self, dt: std::time::Duration) {
+        match self.state {
+            GameState::LevelTransition(elapsed) => self.update_level_transition(dt, elapsed),
+            GameState::Playing => self.update_playing(dt),
+            GameState::Dying => self.update_dying(dt),
+            GameState::Dead => self.update_dead(),
+            GameState::Won => self.update_won(),
+            GameState::LevelComplete => self.update_level_complete(),
+            _ => (),
+        }
+        //clear out the up key event, now that the update funcs have had a chance to see it
+        self.up_key = None;
+    }
+
+    fn log_answer(&mut self, alien_index: usize, outcome: Outcome) {
+        let alien = &self.aliens[alien_index];
+        let boss_hit = !alien.chain.is_empty();
+        let record = AnswerRecord {
+            session: self.session,
+            seed: self.seed,
+            timestamp: now(),
+            level_id: self.level().id(),
+            wave: self.current_wave,
+            difficulty: self.difficulty_tier().name,
+            operation: alien.problem.operation,
+            operands: alien.problem.operands.clone(),
+            problem: alien.problem.display_text.clone(),
+            answer: alien.problem.answer,
+            attempts: alien.attempts.clone(),
+            time_ms: alien.time_ms as u32,
+            outcome,
+        };
+        self.mastery.record(&record);
+        if self.mode == GameMode::Practice || self.mode == GameMode::MadMinute {
+            self.practice_summary.record(&record);
+        }
+        self.events.push(match outcome {
+            Outcome::Correct if boss_hit => GameEvent::BossHit(record),
+            Outcome::Correct => GameEvent::AlienDestroyed(record),
+            Outcome::ReachedBottom => GameEvent::AlienReachedBottom(record),
+        });
+    }
+
+    fn load_level_wave(&mut self, level: usize, wave: usize) {
+        self.current_level = level;
+        self.current_wave = wave;
+        self.target = None;
+        let level = match &self.custom_level {
+            Some(level) => level,
+            None => &self.levels[self.current_level],
+        };
+        let wave = &level.waves[self.current_wave];
+        let mastery = if self.adaptive {
+            Some(&self.mastery)
+        } else {
+            None
+        };
+        let difficulty = self.difficulty_tier();
+        self.aliens = gen_aliens(wave, &difficulty, &mut self.generator, mastery);
+        self.target = get_lowest_living_alien(&self.aliens);
+    }
+
+    fn set_level_wave(&mut self, level: usize, wave: usize) {
+        if level > self.current_level {
+            self.state = GameState::LevelComplete;
+        }
+        self.load_level_wave(level, wave);
+    }
+
+    fn increment_level_wave(&mut self) {
+        if self.mode == GameMode::Endless {
+            // there is always another wave
+            let wave = endless_wave(self.current_wave + 1, &mut self.generator.rng);
+            if let Some(level) = &mut self.custom_level {
+                level.waves.push(wave);
+            }
+        }
+        //if we were at the last wave already then go to next level
+        if self.current_wave + 1 >= self.level().waves.len() {
+            if self.mode == GameMode::Practice {
+                self.finish_practice();
+            } else if self.current_level + 1 >= self.levels.len() {
+                self.events.push(GameEvent::LevelCompleted {
+                    level: self.current_level,
+                    unlocked: None,
+                    score: self.score.points,
+                });
+                self.events.push(GameEvent::GameWon);
+                self.state = GameState::Won;
+            } else {
+                //unlock the next level
+                self.events.push(GameEvent::LevelCompleted {
+                    level: self.current_level,
+                    unlocked: Some(self.current_level + 1),
+                    score: self.score.points,
+                });
+                self.set_level_wave(self.current_level + 1, 0)
+            }
+        } else {
+            self.events.push(GameEvent::WaveCleared {
+                wave: self.current_wave,
+            });
+            self.set_level_wave(self.current_level, self.current_wave + 1);
+        }
+    }
+
+    fn apply_penalty(&mut self) {
+        let (penalty, reveal_after) = {
+            let level = self.level();
+            (level.penalty, level.reveal_after)
+        };
+        match penalty {
+            PenaltyMode::None => (),
+            PenaltyMode::Lockout => self.turret.lockout = LOCKOUT_MS,
+            PenaltyMode::SpeedUp => {
+                if let Some(alien_index) = self.target {
+                    self.aliens[alien_index].speed *= SPEED_UP_PENALTY;
+                }
+            }
+            PenaltyMode::ScorePenalty => {
+                self.score.points = self.score.points.saturating_sub(SCORE_PENALTY)
+            }
+        }
+        // every attempt so far on the target was wrong
+        if let (Some(reveal_after), Some(alien_index)) = (reveal_after, self.target) {
+            let alien = &mut self.aliens[alien_index];
+            if alien.attempts.len() as u32 >= reveal_after {
+                alien.revealed = true;
+            }
+        }
+    }
+
+    // swap each dead alien for a new one above the screen, so a mad
+    // minute never runs out of aliens
+    fn respawn_aliens(&mut self) {
+        let difficulty = self.difficulty_tier();
+        let mastery = if self.adaptive {
+            Some(&self.mastery)
+        } else {
+            None
+        };
+        let level = match &self.custom_level {
+            Some(level) => level,
+            None => &self.levels[self.current_level],
+        };
+        let groups = &level.waves[self.current_wave].groups;
+        for i in 0..self.aliens.len() {
+            if self.aliens[i].state != AlienState::Dead {
+                continue;
+            }
+            let group = &groups[self.generator.rng.gen_range(0, groups.len())];
+            // keep clear of the other aliens still coming in
+            let mut x: f32 = self.generator.rng.gen_range(0.05, 0.95);
+            for _ in 0..10 {
+                if !self
+                    .aliens
+                    .iter()
+                    .any(|alien| alien.pos[1] < 0.1 && (alien.pos[0] - x).abs() < 0.1)
+                {
+                    break;
+                }
+                x = self.generator.rng.gen_range(0.05, 0.95);
+            }
+            let y = -0.1 - self.generator.rng.gen_range(0.0, 0.3);
+            let pos = na::Point2::new(x, y);
+            self.aliens[i] = gen_alien(group, &difficulty, &mut self.generator, mastery, pos);
+            if self.target == Some(i) {
+                self.target = get_lowest_living_alien(&self.aliens);
+            }
+        }
+    }
+
+    fn update_won(&mut self) {
+        if let Some(keycode) = self.up_key {
+            if keycode == KeyCode::Return {
+                self.state = GameState::DifficultySelect;
+            }
+        }
+    }
+
+    fn update_level_complete(&mut self) {
+        self.turret.rotation = 0.0;
+        if let Some(keycode) = self.up_key {
+            if keycode == KeyCode::Return {
+                self.state = GameState::LevelTransition(0.0);
+                self.events.push(GameEvent::WarpStarted {
+                    level: self.current_level,
+                });
+            }
+        }
+    }
+
+    fn update_level_transition(&mut self, dt: std::time::Duration, elapsed: f32) {
+        self.state = GameState::LevelTransition(elapsed + dt.as_millis() as f32);
+        let pct = elapsed / 3000.0;
+        self.turret.pos[1] -= 0.015 * pct;
+
+        if elapsed >= 3000.0 {
+            self.state = GameState::Playing;
+            self.score = Score::default();
+            self.events.push(GameEvent::LevelStarted {
+                level: self.current_level,
+            });
+            self.turret.pos = na::Point2::new(0.5, 0.9); //put turret back at the bottom
+        }
+    }
+
+    fn update_dead(&mut self) {
+        if let Some(keycode) = self.up_key {
+            if keycode == KeyCode::Return {
+                self.state = if self.mode == GameMode::Endless {
+                    GameState::ModeSelect
+                } else {
+                    GameState::LevelSelect
+                };
+            }
+        }
+    }
+
+    fn update_dying(&mut self, dt: std::time::Duration) {
+        for alien in &mut self.aliens {
+            alien.update(&mut self.turret, dt);
+        }
+        self.turret.update(dt);
+        for splosion in &mut self.turret.explosions {
+            splosion.update(dt);
+        }
+        if self
+            .turret
+            .explosions
+            .iter()
+            .all(|splosion| splosion.elapsed - splosion.start_time > splosion.duration)
+        {
+            if self.lives > 0 {
+                self.lives -= 1;
+                self.turret = Turret::new(&mut self.generator.rng);
+                self.set_level_wave(self.current_level, self.current_wave);
+                self.state = GameState::Playing;
+                self.events.push(GameEvent::LifeLost {
+                    wave: self.current_wave,
+                    lives_left: self.lives,
+                });
+            } else {
+                self.state = GameState::Dead;
+                if self.mode == GameMode::Endless {
+                    self.finish_endless();
+                }
+                self.events.push(GameEvent::GameOver);
+            }
+        }
+    }
+
+    fn update_playing(&mut self, dt: std::time::Duration) {
+        self.elapsed_ms += dt.as_millis() as u64;
+        if let Some(keycode) = self.up_key {
+            if keycode == KeyCode::Return && self.turret.lockout > 0.0 {
+                // locked out after a wrong answer, keep what was typed
+            } else if keycode == KeyCode::Return {
+                // typing that isn't an answer at all is ignored
+                if self.turret.raw_text.parse::<Answer>().is_ok() {
+                    match self.target {
+                        Some(alien_index)
+                            if self.aliens[alien_index].problem.is_answer(&self.turret.raw_text) =>
+                        {
+                            self.aliens[alien_index]
+                                .attempts
+                                .push(self.turret.raw_text.clone());
+                            self.log_answer(alien_index, Outcome::Correct);
+                            let alien = &self.aliens[alien_index];
+                            self.score.hit(alien.operation, alien.pos[1], alien.time_ms);
+                            if self.aliens[alien_index].chain.is_empty() {
+                                self.aliens[alien_index].state = AlienState::Exploding;
+                            } else {
+                                self.aliens[alien_index].next_problem();
+                            }
+                            self.turret.state = TurretState::Firing;
+                        }
+                        _ => {
+                            if let Some(alien_index) = self.target {
+                                self.aliens[alien_index]
+                                    .attempts
+                                    .push(self.turret.raw_text.clone());
+                            }
+                            self.score.miss();
+                            self.turret.wrong_answer();
+                            self.apply_penalty();
+                            self.events.push(GameEvent::WrongAnswer);
+                        }
+                    }
+                }
+                self.turret.raw_text = "".to_string();
+            } else if keycode == KeyCode::Back {
+                let _ = self.turret.raw_text.pop();
+            } else if keycode == KeyCode::Left {
+                if let Some(index) = self.target {
+                    if self
+                        .aliens
+                        .iter()
+                        .any(|alien| alien.state == AlienState::Alive && alien.pos[1] >= 0.0)
+                    {
+                        let mut i = if index == 0 {
+                            self.aliens.len() - 1
+                        } else {
+                            index - 1
+                        };
+                        while self.aliens[i].state != AlienState::Alive
+                            || self.aliens[i].pos[1] < 0.0
+                        {
+                            i = if i == 0 { self.aliens.len() - 1 } else { i - 1 }
+                        }
+                        self.target = Some(i);
+                    }
+                }
+            } else if keycode == KeyCode::Right {
+                if let Some(index) = self.target {
+                    if self
+                        .aliens
+                        .iter()
+                        .any(|alien| alien.state == AlienState::Alive && alien.pos[1] > 0.0)
+                    {
+                        let mut i = (index + 1) % self.aliens.len();
+                        while self.aliens[i].state != AlienState::Alive
+                            || self.aliens[i].pos[1] < 0.0
+                        {
+                            i = (i + 1) % self.aliens.len();
+                        }
+                        self.target = Some(i);
+                    }
+                }
+            }
+        }
+
+        //update aliens and turret
+        for alien in &mut self.aliens {
+            alien.update(&mut self.turret, dt);
+        }
+        self.turret.update(dt);
+        if self.mode == GameMode::Practice {
+            // practice aliens wait above the turret instead of landing
+            for alien in &mut self.aliens {
+                alien.pos[1] = alien.pos[1].min(PRACTICE_STOP_Y);
+            }
+        }
+
+        // If there is a target, rotate the turret to it
+        match self.target {
+            Some(target) if self.aliens[target].state != AlienState::Dead => {
+                let turret_pos = self.turret.pct_pos();
+                let turret_vector: na::Vector2<f32> =
+                    na::Vector2::new(turret_pos[0], turret_pos[1]);
+                let alien_pos = self.aliens[target].pct_pos();
+                let alien_vector = na::Vector2::new(alien_pos[0], alien_pos[1]);
+                let v1 = na::Vector2::new(0.0, -1.0);
+                let v2 = alien_vector - turret_vector;
+                let mut angle = v2.angle(&v1);
+                if alien_pos[0] < 0.5 {
+                    angle = -angle;
+                }
+                self.turret.rotation = angle;
+            }
+            Some(_) => self.target = get_lowest_living_alien(&self.aliens),
+            None => (),
+        };
+
+        if self.mode == GameMode::MadMinute {
+            // landing only costs the fact, the alien is replaced below
+            for index in 0..self.aliens.len() {
+                let alien = &self.aliens[index];
+                if alien.state == AlienState::Alive && alien.pos[1] > 0.9 {
+                    self.log_answer(index, Outcome::ReachedBottom);
+                    self.score.miss();
+                    self.aliens[index].state = AlienState::Dead;
+                }
+            }
+            self.respawn_aliens();
+            if self.elapsed_ms >= MAD_MINUTE_MS {
+                self.finish_practice();
+            }
+            // no waves to clear or lives to lose
+            return;
+        }
+
+        // Find the alien furthest down the screen, if its at the bottom, dead.
+        if let Some((index, alien)) = self
+            .aliens
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, alien)| (alien.pos[1] * 1000.0) as i32)
+        {
+            if alien.pos[1] > 0.9 {
+                if alien.state == AlienState::Alive {
+                    self.log_answer(index, Outcome::ReachedBottom);
+                    self.score.miss();
+                }
+                self.state = GameState::Dying
+            };
+        }
+
+        //If all aliens are dead, increment the wave/level
+        if self
+            .aliens
+            .iter()
+            .all(|alien| alien.state == AlienState::Dead)
+        {
+            self.increment_level_wave();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    fn playing(seed: u64) -> Simulation {
+        let mut sim = Simulation::new(Level::new(), Difficulty::new(), seed);
+        sim.start_level(0);
+        sim.update(FRAME);
+        sim.events.clear();
+        sim
+    }
+
+    // lands the lowest alien, then lets the turret finish exploding
+    fn land_alien(sim: &mut Simulation) {
+        let index = get_lowest_living_alien(&sim.aliens).unwrap();
+        sim.aliens[index].pos[1] = 0.95;
+        sim.update(FRAME);
+        assert_eq!(sim.state, GameState::Dying);
+        for _ in 0..1000 {
+            if sim.state != GameState::Dying {
+                break;
+            }
+            sim.update(Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn correct_answer_destroys_target() {
+        let mut sim = playing(1);
+        let target = sim.target.unwrap();
+        for ch in sim.aliens[target].problem.answer_text().chars() {
+            sim.text_input(ch);
+        }
+        sim.key_up(KeyCode::Return);
+        sim.update(FRAME);
+
+        assert!(sim.aliens[target].state == AlienState::Exploding);
+        assert!(sim.score.points > 0);
+        assert!(sim.turret.raw_text.is_empty());
+        assert!(sim
+            .events
+            .iter()
+            .any(|event| matches!(event, GameEvent::AlienDestroyed(_))));
+    }
+
+    #[test]
+    fn landing_costs_a_life_then_the_game() {
+        let mut sim = playing(2);
+        let lives = sim.lives;
+        assert!(lives > 0);
+
+        land_alien(&mut sim);
+        assert_eq!(sim.state, GameState::Playing);
+        assert_eq!(sim.lives, lives - 1);
+        assert!(sim
+            .events
+            .iter()
+            .any(|event| matches!(event, GameEvent::AlienReachedBottom(_))));
+        assert!(sim
+            .events
+            .iter()
+            .any(|event| matches!(event, GameEvent::LifeLost { .. })));
+
+        sim.lives = 0;
+        sim.events.clear();
+        land_alien(&mut sim);
+        assert_eq!(sim.state, GameState::Dead);
+        assert!(sim
+            .events
+            .iter()
+            .any(|event| matches!(event, GameEvent::GameOver)));
+    }
+
+    #[test]
+    fn wrong_answer_misses() {
+        let mut sim = playing(4);
+        let target = sim.target.unwrap();
+        sim.score.streak = 3;
+        for ch in "9999".chars() {
+            sim.text_input(ch);
+        }
+        sim.key_up(KeyCode::Return);
+        sim.update(FRAME);
+
+        assert!(sim.aliens[target].state == AlienState::Alive);
+        assert_eq!(sim.aliens[target].attempts, vec!["9999"]);
+        assert_eq!(sim.score.points, 0);
+        assert_eq!(sim.score.streak, 0);
+        assert!(sim.turret.raw_text.is_empty());
+        assert!(sim
+            .events
+            .iter()
+            .any(|event| matches!(event, GameEvent::WrongAnswer)));
+    }
+
+    #[test]
+    fn clearing_waves_moves_through_the_level() {
+        let mut sim = playing(5);
+        let waves = sim.level().waves.len();
+        for wave in 0..waves {
+            assert_eq!((sim.current_level, sim.current_wave), (0, wave));
+            for alien in &mut sim.aliens {
+                alien.state = AlienState::Dead;
+            }
+            sim.update(FRAME);
+            let cleared = sim.events.iter().any(|event| match event {
+                GameEvent::WaveCleared { wave: cleared } => *cleared == wave,
+                GameEvent::LevelCompleted { level, unlocked, .. } => {
+                    wave == waves - 1 && *level == 0 && *unlocked == Some(1)
+                }
+                _ => false,
+            });
+            assert!(cleared, "wave {}", wave);
+            sim.events.clear();
+            for _ in 0..1000 {
+                if sim.state == GameState::Playing {
+                    break;
+                }
+                sim.update(Duration::from_millis(100));
+            }
+        }
+        assert_eq!((sim.current_level, sim.current_wave), (1, 0));
+        assert!(sim
+            .aliens
+            .iter()
+            .all(|alien| alien.state == AlienState::Alive));
+    }
+
+    #[test]
+    fn quitting_endless_finishes_the_run() {
+        let mut sim = Simulation::new(Level::new(), Difficulty::new(), 3);
+        sim.start_endless();
+        sim.update(FRAME);
+        sim.events.clear();
+
+        sim.finish_endless();
+        assert_eq!(sim.state, GameState::Dead);
+        assert!(sim
+            .events
+            .iter()
+            .any(|event| matches!(event, GameEvent::EndlessFinished { .. })));
+    }
+}
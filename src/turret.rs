@@ -14,31 +14,31 @@ pub enum TurretState {
     //todo Rotating?
 }
 
+pub const WRONG_FLASH_MS: f32 = 900.0;
+
 pub struct Turret {
     pub rotation: f32,
     pub raw_text: String,
-    pub text: MBText,
     pub explosions: Vec<Explosion>,
     pub state: TurretState,
-    pub src_pixel_width: f32,
-    pub src_pixel_height: f32,
-    pub pos: na::Point2<f32>,    
+    pub pos: na::Point2<f32>,
+    // millis left before the turret can fire again after a wrong answer
+    pub lockout: f32,
+    // the last wrong answer, flashed for WRONG_FLASH_MS
+    pub wrong_text: String,
+    pub wrong_flash: f32,    
 }
 
-impl Scalable for Turret {
+impl Positioned for Turret {
     fn pct_pos(&self) -> na::Point2<f32> {
         self.pos
     }
     fn pct_dimensions(&self) -> (f32, f32) {
         (0.030, 0.05)
     }
-    fn src_pixel_dimensions(&self) -> (f32, f32) {
-        (self.src_pixel_width, self.src_pixel_height)
-    }
 }
 impl Turret {
-    pub fn new(assets: &Assets, ctx: &mut Context) -> Turret {
-        let mut rng = rand::thread_rng();
+    pub fn new<R: Rng>(rng: &mut R) -> Turret {
         let mut explosions = Vec::new();
         for _ in 0..20 {
             let r1 = rng.gen_range(-0.05, 0.05);
@@ -50,31 +50,45 @@ impl Turret {
         Turret {
             rotation: 0.0,
             raw_text: "".to_string(),
-            text: MBText::new("".to_string(), &assets.number_font, WHITE, 24.0, ctx),
             explosions: explosions,
             state: TurretState::Resting,
-            src_pixel_width: assets.turret.width() as f32,
-            src_pixel_height: assets.turret.height() as f32,
             pos: na::Point2::new(0.5, 0.9),
+            lockout: 0.0,
+            wrong_text: "".to_string(),
+            wrong_flash: 0.0,
         }
     }
 
-    pub fn update(&mut self, _ctx: &mut Context, _dt: std::time::Duration) {}
+    pub fn update(&mut self, dt: std::time::Duration) {
+        let millis = dt.as_millis() as f32;
+        self.lockout = (self.lockout - millis).max(0.0);
+        self.wrong_flash = (self.wrong_flash - millis).max(0.0);
+    }
+
+    pub fn wrong_answer(&mut self) {
+        self.wrong_text = self.raw_text.clone();
+        self.wrong_flash = WRONG_FLASH_MS;
+    }
 
-    pub fn draw(&self, ctx: &mut Context, assets: &mut Assets) {
+    // the wrong answer blinks on and off while it flashes
+    pub fn showing_wrong_answer(&self) -> bool {
+        self.wrong_flash > 0.0 && (self.wrong_flash / 150.0) as i32 % 2 == 0
+    }
+
+    // text is the player's typed answer, built from raw_text by the caller
+    pub fn draw(&self, text: &MBText, ctx: &mut Context, assets: &mut Assets) {
         let param = DrawParam::new()
             .color(WHITE)
-            .scale(self.scale(graphics::size(ctx)))
+            .scale(self.scale_from(image_dimensions(&assets.turret), graphics::size(ctx)))
             .offset(na::Point2::new(0.5, 0.5))
             .rotation(self.rotation)
             .dest(self.pixel_pos(graphics::size(ctx)));
         let _ = graphics::draw(ctx, &assets.turret, param);
-        self.text
-            .draw_horizontal_center(graphics::size(ctx).1 * 0.9, ctx);
+        text.draw_horizontal_center(graphics::size(ctx).1 * 0.9, ctx);
     }
 
     pub fn draw_lives(&self, lives: usize, ctx: &mut Context, assets: &mut Assets) {
-        let scale = self.scale(graphics::size(ctx));
+        let scale = self.scale_from(image_dimensions(&assets.turret), graphics::size(ctx));
 
         for i in 0..lives {
             let param = DrawParam::new()
@@ -88,4 +102,17 @@ impl Turret {
             let _ = graphics::draw(ctx, &assets.turret, param);
         }
     }
+
+    // the score sits just left of the lives
+    pub fn draw_score(&self, text: &MBText, ctx: &mut Context) {
+        let window_dimensions = graphics::size(ctx);
+        let text_dimensions = text.dest_pixel_dimensions(window_dimensions);
+        text.draw(
+            na::Point2::new(
+                0.93 * window_dimensions.0 - text_dimensions.0,
+                0.925 * window_dimensions.1 - text_dimensions.1 / 2.0,
+            ),
+            ctx,
+        );
+    }
 }